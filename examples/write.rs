@@ -1,7 +1,7 @@
 use std::{env, fs::File};
 
 use brickadia::{
-    save::{Brick, BrickColor, BrickOwner, Color, Preview, SaveData, Size, User},
+    save::{Brick, BrickColor, Color, Preview, SaveData, Size, User},
     write::SaveWriter,
 };
 
@@ -11,21 +11,12 @@ fn main() {
         id: "3f5108a0-c929-4e77-a115-21f65096887b".parse().unwrap(),
     };
 
-    let mut save = SaveData::default();
-
-    // set the first header
-    save.header1.author = me.clone();
-    save.header1.host = Some(me.clone());
+    // create a save authored by, hosted by, and owned by `me`
+    let mut save = SaveData::new(me, "Plate");
     save.header1.description = "This was saved with brickadia-rs!".into();
 
-    // set the second header
-    save.header2
-        .brick_owners
-        .push(BrickOwner::from_user_bricks(me.clone(), 100));
-
     // set the preview image
-    let preview_bytes = std::fs::read("examples/write_preview.png").unwrap();
-    save.preview = Preview::PNG(preview_bytes);
+    save.preview = Preview::load_png("examples/write_preview.png").unwrap();
 
     // add some bricks
     for y in 0..10 {
@@ -39,11 +30,15 @@ fn main() {
                     b: (y as f32 / 10.0 * 255.0) as u8,
                     a: 255,
                 }),
+                owner_index: 1,
                 ..Default::default()
             });
         }
     }
 
+    // count the bricks placed by each owner
+    save.normalize_owners();
+
     // write out the save
     let save_location = env::args()
         .nth(1)
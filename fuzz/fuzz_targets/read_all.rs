@@ -0,0 +1,10 @@
+#![no_main]
+
+use std::io::Cursor;
+
+use brickadia::read::SaveReader;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = SaveReader::new(Cursor::new(data)).and_then(|mut reader| reader.read_all());
+});
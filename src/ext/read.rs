@@ -10,39 +10,81 @@ use uuid::Uuid;
 
 use crate::save::{Color, UnrealType};
 
+/// The most elements to allocate up front when reading an array, so a corrupt length
+/// can't trigger a huge allocation before any data is read.
+const MAX_ARRAY_PREALLOCATION: usize = 4096;
+
+/// The number of bytes to read at a time when reading a run of bytes of unknown validity.
+const BYTE_CHUNK_SIZE: usize = 4096;
+
+fn invalid_length() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid length")
+}
+
+/// Decode UTF-8 string data, replacing invalid sequences with `U+FFFD` when `lossy`.
+fn decode_utf8(chars: Vec<u8>, lossy: bool) -> Result<String> {
+    match String::from_utf8(chars) {
+        Ok(string) => Ok(string),
+        Err(e) if lossy => Ok(String::from_utf8_lossy(e.as_bytes()).into_owned()),
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid string data",
+        )),
+    }
+}
+
 pub trait ReadExt: Read {
     fn read_string(&mut self) -> Result<String> {
+        self.read_string_with(false)
+    }
+
+    /// Read a string, replacing invalid UTF-8 sequences instead of failing.
+    fn read_string_lossy(&mut self) -> Result<String> {
+        self.read_string_with(true)
+    }
+
+    /// Read a string, only failing on invalid UTF-8 when `lossy` is false.
+    fn read_string_with(&mut self, lossy: bool) -> Result<String> {
         match self.read_i32::<LittleEndian>()? {
             size if size >= 0 => {
-                let mut chars = vec![0u8; cmp::max(0, size - 1) as usize];
-                self.read_exact(&mut chars)?;
+                let chars = self.read_byte_vec(cmp::max(0, size - 1) as usize)?;
                 if size > 0 {
                     self.read_u8()?;
                 } // read a null terminator
-                String::from_utf8(chars)
-                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid string data"))
+                decode_utf8(chars, lossy)
             }
-            size if size < 0 => {
-                let size = -size;
+            size => {
+                let size = size.unsigned_abs() as usize;
                 match size % 2 {
                     0 => {
-                        let mut chars = vec![0; size as usize / 2];
-                        self.read_u16_into::<LittleEndian>(&mut chars)?;
+                        let bytes = self.read_byte_vec(size)?;
+                        let chars = bytes
+                            .chunks_exact(2)
+                            .map(LittleEndian::read_u16)
+                            .collect::<Vec<_>>();
                         String::from_utf16(&chars).map_err(|_| {
                             io::Error::new(io::ErrorKind::InvalidData, "invalid UCS-2 string data")
                         })
                     }
-                    1 => Err(io::Error::new(
+                    _ => Err(io::Error::new(
                         io::ErrorKind::InvalidData,
                         "invalid UCS-2 size",
                     )),
-                    _ => unreachable!(),
                 }
             }
-            _ => unreachable!(),
         }
     }
 
+    /// Read exactly `len` bytes, only allocating as data is actually read.
+    fn read_byte_vec(&mut self, len: usize) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(cmp::min(len, BYTE_CHUNK_SIZE));
+        self.take(len as u64).read_to_end(&mut bytes)?;
+        if bytes.len() != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(bytes)
+    }
+
     fn read_uuid(&mut self) -> Result<Uuid> {
         let mut le_bytes = [0; 4];
         self.read_u32_into::<LittleEndian>(&mut le_bytes)?;
@@ -62,7 +104,10 @@ pub trait ReadExt: Read {
         F: FnMut(&mut Self) -> Result<T>,
     {
         let len = self.read_i32::<LittleEndian>()?;
-        let mut vec = Vec::with_capacity(len as usize);
+        if len < 0 {
+            return Err(invalid_length());
+        }
+        let mut vec = Vec::with_capacity(cmp::min(len as usize, MAX_ARRAY_PREALLOCATION));
         for _ in 0..len {
             vec.push(operation(self)?);
         }
@@ -78,7 +123,10 @@ pub trait BitReadExt: BitRead {
         F: FnMut(&mut Self) -> Result<T>,
     {
         let len = self.read_i32_le()?;
-        let mut vec = Vec::with_capacity(len as usize);
+        if len < 0 {
+            return Err(invalid_length());
+        }
+        let mut vec = Vec::with_capacity(cmp::min(len as usize, MAX_ARRAY_PREALLOCATION));
         for _ in 0..len {
             vec.push(operation(self)?);
         }
@@ -86,14 +134,17 @@ pub trait BitReadExt: BitRead {
     }
 
     fn read_uint(&mut self, max: u32) -> Result<u32> {
-        let mut value = 0;
-        let mut mask = 1;
+        if max < 2 {
+            return Ok(0);
+        }
 
-        while value + mask < max && mask != 0 {
-            if self.read_bit()? {
-                value |= mask;
-            }
-            mask <<= 1;
+        // the low bits below the highest set bit of `max` are always present, so read them
+        // all at once; the highest bit is only present if it could still keep the value in range
+        let bits = 31 - max.leading_zeros();
+        let mut value = self.read::<u32>(bits)?;
+        let mask = 1u64 << bits;
+        if value as u64 + mask < max as u64 && self.read_bit()? {
+            value |= mask as u32;
         }
 
         Ok(value)
@@ -123,37 +174,52 @@ pub trait BitReadExt: BitRead {
     }
 
     fn read_string(&mut self) -> Result<String> {
+        self.read_string_with(false)
+    }
+
+    /// Read a string, replacing invalid UTF-8 sequences instead of failing.
+    fn read_string_lossy(&mut self) -> Result<String> {
+        self.read_string_with(true)
+    }
+
+    /// Read a string, only failing on invalid UTF-8 when `lossy` is false.
+    fn read_string_with(&mut self, lossy: bool) -> Result<String> {
         match self.read_i32_le()? {
             size if size >= 0 => {
-                let mut chars = vec![0u8; cmp::max(0, size - 1) as usize];
-                self.read_bytes(&mut chars)?;
+                let chars = self.read_byte_vec(cmp::max(0, size - 1) as usize)?;
                 if size > 0 {
                     self.read_bytes(&mut [0])?;
                 } // read a null terminator
-                String::from_utf8(chars)
-                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid string data"))
+                decode_utf8(chars, lossy)
             }
-            size if size < 0 => {
-                let size = -size * 2;
-                match size % 2 {
-                    0 => {
-                        let mut chars = vec![0; (size / 2) as usize];
-                        self.read_u16_le_into(&mut chars)?;
-                        String::from_utf16(&chars).map_err(|_| {
-                            io::Error::new(io::ErrorKind::InvalidData, "invalid UCS-2 string data")
-                        })
-                    }
-                    1 => Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "invalid UCS-2 size",
-                    )),
-                    _ => unreachable!(),
+            size => {
+                let len = size.unsigned_abs() as usize;
+                let mut chars = Vec::with_capacity(cmp::min(len, BYTE_CHUNK_SIZE));
+                let mut chunk = [0u16; BYTE_CHUNK_SIZE];
+                while chars.len() < len {
+                    let n = cmp::min(len - chars.len(), BYTE_CHUNK_SIZE);
+                    self.read_u16_le_into(&mut chunk[..n])?;
+                    chars.extend_from_slice(&chunk[..n]);
                 }
+                String::from_utf16(&chars).map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "invalid UCS-2 string data")
+                })
             }
-            _ => unreachable!(),
         }
     }
 
+    /// Read exactly `len` bytes, only allocating as data is actually read.
+    fn read_byte_vec(&mut self, len: usize) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(cmp::min(len, BYTE_CHUNK_SIZE));
+        let mut chunk = [0u8; BYTE_CHUNK_SIZE];
+        while bytes.len() < len {
+            let n = cmp::min(len - bytes.len(), BYTE_CHUNK_SIZE);
+            self.read_bytes(&mut chunk[..n])?;
+            bytes.extend_from_slice(&chunk[..n]);
+        }
+        Ok(bytes)
+    }
+
     fn read_i32_le(&mut self) -> Result<i32> {
         let mut bytes = [0u8; 4];
         self.read_bytes(&mut bytes)?;
@@ -189,10 +255,10 @@ pub trait BitReadExt: BitRead {
         Ok(LittleEndian::read_f32(&bytes))
     }
 
-    fn read_unreal_type(&mut self, t: &str) -> Result<UnrealType> {
+    fn read_unreal_type(&mut self, t: &str, lossy: bool) -> Result<UnrealType> {
         match t {
-            "Class" | "Object" => Ok(UnrealType::Class(self.read_string()?)),
-            "String" => Ok(UnrealType::String(self.read_string()?)),
+            "Class" | "Object" => Ok(UnrealType::Class(self.read_string_with(lossy)?)),
+            "String" => Ok(UnrealType::String(self.read_string_with(lossy)?)),
             "Boolean" => Ok(UnrealType::Boolean(self.read_i32_le()? != 0)),
             "Float" => Ok(UnrealType::Float(self.read_f32_le()?)),
             "Color" => {
@@ -205,6 +271,12 @@ pub trait BitReadExt: BitRead {
                 self.read_bytes(&mut byte)?;
                 Ok(UnrealType::Byte(byte[0]))
             }
+            "LinearColor" => Ok(UnrealType::LinearColor(
+                self.read_f32_le()?,
+                self.read_f32_le()?,
+                self.read_f32_le()?,
+                self.read_f32_le()?,
+            )),
             "Rotator" => Ok(UnrealType::Rotator(
                 self.read_f32_le()?,
                 self.read_f32_le()?,
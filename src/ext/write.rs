@@ -8,7 +8,7 @@ use uuid::Uuid;
 use crate::save::{Color, UnrealType};
 
 pub trait WriteExt: Write {
-    fn write_string(&mut self, string: String) -> io::Result<()> {
+    fn write_string_ref(&mut self, string: &str) -> io::Result<()> {
         if string.is_empty() {
             // write out a 0 and nothing else
             self.write_i32::<LittleEndian>(0)?;
@@ -55,7 +55,7 @@ pub trait WriteExt: Write {
         Ok(())
     }
 
-    fn write_color_bgra(&mut self, color: Color) -> io::Result<()> {
+    fn write_color_bgra(&mut self, color: &Color) -> io::Result<()> {
         self.write_u8(color.b)?;
         self.write_u8(color.g)?;
         self.write_u8(color.r)?;
@@ -63,13 +63,13 @@ pub trait WriteExt: Write {
         Ok(())
     }
 
-    fn write_array<F: FnMut(&mut Self, T) -> io::Result<()>, T>(
+    fn write_array_ref<F: FnMut(&mut Self, &T) -> io::Result<()>, T>(
         &mut self,
-        vec: Vec<T>,
+        slice: &[T],
         mut operation: F,
     ) -> io::Result<()> {
-        self.write_i32::<LittleEndian>(vec.len() as i32)?;
-        for item in vec.into_iter() {
+        self.write_i32::<LittleEndian>(slice.len() as i32)?;
+        for item in slice {
             operation(self, item)?;
         }
         Ok(())
@@ -92,6 +92,10 @@ pub trait BitWriteExt: BitWrite {
     }
 
     fn write_string(&mut self, string: String) -> io::Result<()> {
+        self.write_string_ref(&string)
+    }
+
+    fn write_string_ref(&mut self, string: &str) -> io::Result<()> {
         if string.is_empty() {
             self.write_i32(0)?;
             return Ok(());
@@ -186,6 +190,12 @@ pub trait BitWriteExt: BitWrite {
             UnrealType::String(str) => self.write_string(str)?,
             UnrealType::Color(color) => self.write_bytes(&[color.b, color.g, color.r, color.a])?,
             UnrealType::Float(float) => self.write_f32(float)?,
+            UnrealType::LinearColor(r, g, b, a) => {
+                self.write_f32(r)?;
+                self.write_f32(g)?;
+                self.write_f32(b)?;
+                self.write_f32(a)?;
+            }
             UnrealType::Rotator(x, y, z) => {
                 self.write_f32(x)?;
                 self.write_f32(y)?;
@@ -11,6 +11,9 @@ pub mod write;
 #[cfg(feature = "util")]
 pub mod util;
 
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+
 static MAGIC_BYTES: &[u8; 3] = b"BRS";
 
 /// The current save version that can be read by brickadia-rs.
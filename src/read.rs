@@ -4,7 +4,7 @@ use std::{
     cmp,
     collections::HashMap,
     convert::TryFrom,
-    io::{self, Cursor, Read},
+    io::{self, Cursor, Read, Seek, SeekFrom},
 };
 
 use bitstream_io::{BitRead, BitReader};
@@ -12,7 +12,10 @@ use byteorder::{LittleEndian, ReadBytesExt};
 use flate2::read::ZlibDecoder;
 use thiserror::Error;
 
-use crate::{ext::*, save::*, MAGIC_BYTES};
+use crate::{ext::*, save::*, MAGIC_BYTES, SAVE_VERSION};
+
+/// The most bricks that will be read from a single save, regardless of its brick count.
+const MAX_BRICK_COUNT: usize = 100_000_000;
 
 lazy_static::lazy_static! {
     static ref DEFAULT_MATERIALS: Vec<String> = vec!["BMC_Hologram", "BMC_Plastic", "BMC_Glow", "BMC_Metallic", "BMC_Glass"].into_iter().map(|s| s.into()).collect();
@@ -33,6 +36,67 @@ pub enum ReadError {
     BadSectionReadOrder,
     #[error("invalid compressed section")]
     InvalidCompression,
+    #[error("invalid component data")]
+    InvalidComponentData,
+    #[error("unrecognized save version {found} (newest supported is {max_supported})")]
+    UnrecognizedVersion { found: u16, max_supported: u16 },
+}
+
+impl ReadError {
+    /// Whether or not this error came from the underlying reader.
+    #[inline]
+    pub fn is_io(&self) -> bool {
+        matches!(self, ReadError::IoError(_))
+    }
+
+    /// Whether or not this error was caused by invalid save data.
+    #[inline]
+    pub fn is_format(&self) -> bool {
+        matches!(
+            self,
+            ReadError::BadHeader
+                | ReadError::InvalidDataHeader1
+                | ReadError::InvalidDataHeader2
+                | ReadError::InvalidCompression
+                | ReadError::InvalidComponentData
+                | ReadError::UnrecognizedVersion { .. }
+        )
+    }
+
+    /// Whether or not this error was caused by reading sections out of order.
+    #[inline]
+    pub fn is_order(&self) -> bool {
+        matches!(self, ReadError::BadSectionReadOrder)
+    }
+
+    /// Extract the inner `io::Error`, if this error came from the underlying reader.
+    #[inline]
+    pub fn into_io_error(self) -> Option<io::Error> {
+        match self {
+            ReadError::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+type ComponentBitReader = BitReader<Cursor<Vec<u8>>, bitstream_io::LittleEndian>;
+
+/// Pick a string reading function for header data, replacing invalid UTF-8 when `lenient`.
+fn string_reader(lenient: bool) -> fn(&mut Cursor<Vec<u8>>) -> io::Result<String> {
+    if lenient {
+        |r| r.read_string_lossy()
+    } else {
+        |r| r.read_string()
+    }
+}
+
+/// Pick a string reading function for component data, replacing invalid UTF-8 when `lenient`.
+fn bit_string_reader(lenient: bool) -> fn(&mut ComponentBitReader) -> io::Result<String> {
+    if lenient {
+        |r| r.read_string_lossy()
+    } else {
+        |r| r.read_string()
+    }
 }
 
 /// A save reader, which reads data from its `reader` (a `Read + Seek`).
@@ -40,6 +104,8 @@ pub struct SaveReader<R: Read> {
     reader: R,
     pub version: u16,
     pub game_version: i32,
+    /// Whether or not to replace invalid UTF-8 in strings instead of failing.
+    pub lenient_strings: bool,
 
     header1_read: bool,
     header2_read: bool,
@@ -48,7 +114,21 @@ pub struct SaveReader<R: Read> {
 
 impl<R: Read> SaveReader<R> {
     /// Create a new save reader from an existing `reader`, a `Read + Seek`.
-    pub fn new(mut reader: R) -> Result<Self, ReadError> {
+    ///
+    /// Fails with [`ReadError::UnrecognizedVersion`] if the save is newer than [`SAVE_VERSION`].
+    pub fn new(reader: R) -> Result<Self, ReadError> {
+        Self::with_version_check(reader, false)
+    }
+
+    /// Like [`new`](SaveReader::new), but allow saves newer than [`SAVE_VERSION`], reading them
+    /// as if they were the newest supported version.
+    ///
+    /// This assumes newer versions only add data to the end of each section, which is skipped.
+    pub fn new_allow_newer_versions(reader: R) -> Result<Self, ReadError> {
+        Self::with_version_check(reader, true)
+    }
+
+    fn with_version_check(mut reader: R, allow_unknown_versions: bool) -> Result<Self, ReadError> {
         let mut magic = [0u8; 3];
         reader.read_exact(&mut magic)?;
         if &magic != MAGIC_BYTES {
@@ -56,6 +136,13 @@ impl<R: Read> SaveReader<R> {
         }
 
         let version = reader.read_u16::<LittleEndian>()?;
+        if version > SAVE_VERSION && !allow_unknown_versions {
+            return Err(ReadError::UnrecognizedVersion {
+                found: version,
+                max_supported: SAVE_VERSION,
+            });
+        }
+
         let game_version = if version >= 8 {
             reader.read_i32::<LittleEndian>()?
         } else {
@@ -65,6 +152,7 @@ impl<R: Read> SaveReader<R> {
         Ok(SaveReader {
             version,
             game_version,
+            lenient_strings: false,
             reader,
             header1_read: false,
             header2_read: false,
@@ -72,6 +160,13 @@ impl<R: Read> SaveReader<R> {
         })
     }
 
+    /// Get a mutable reference to the underlying reader.
+    ///
+    /// Reading from it directly will likely break any following reads from this `SaveReader`.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
     /// Skip the first header.
     pub fn skip_header1(&mut self) -> Result<(), ReadError> {
         skip_compressed(&mut self.reader)?;
@@ -82,15 +177,16 @@ impl<R: Read> SaveReader<R> {
     /// Read the first header.
     pub fn read_header1(&mut self) -> Result<Header1, ReadError> {
         let (mut cursor, _) = read_compressed(&mut self.reader)?;
+        let read_string = string_reader(self.lenient_strings);
 
         // match map: a string
-        let map = cursor.read_string()?;
+        let map = read_string(&mut cursor)?;
 
         // match author name: a string
-        let author_name = cursor.read_string()?;
+        let author_name = read_string(&mut cursor)?;
 
         // match description: a string
-        let description = cursor.read_string()?;
+        let description = read_string(&mut cursor)?;
 
         // match author id: a uuid
         let author_uuid = cursor.read_uuid()?;
@@ -100,7 +196,7 @@ impl<R: Read> SaveReader<R> {
         //         else: not provided
         let host = match self.version {
             _ if self.version >= 8 => {
-                let name = cursor.read_string()?;
+                let name = read_string(&mut cursor)?;
                 let id = cursor.read_uuid()?;
                 Some(User { name, id })
             }
@@ -151,12 +247,13 @@ impl<R: Read> SaveReader<R> {
         }
 
         let (mut cursor, _) = read_compressed(&mut self.reader)?;
+        let read_string = string_reader(self.lenient_strings);
 
         // match mods: an array of strings
-        let mods = cursor.read_array(|r| r.read_string())?;
+        let mods = cursor.read_array(read_string)?;
 
         // match brick assets: an array of strings
-        let brick_assets = cursor.read_array(|r| r.read_string())?;
+        let brick_assets = cursor.read_array(read_string)?;
 
         // match colors: an array of 4 bytes each, BGRA
         let colors = cursor.read_array(|r| -> io::Result<Color> {
@@ -169,7 +266,7 @@ impl<R: Read> SaveReader<R> {
         // version >= 2: an array of strings
         //         else: a list of default materials (see top of file)
         let materials = match self.version {
-            _ if self.version >= 2 => cursor.read_array(|r| r.read_string())?,
+            _ if self.version >= 2 => cursor.read_array(read_string)?,
             _ => DEFAULT_MATERIALS.clone(),
         };
 
@@ -182,13 +279,13 @@ impl<R: Read> SaveReader<R> {
                 match self.version {
                     _ if self.version >= 8 => {
                         let id = r.read_uuid()?;
-                        let name = r.read_string()?;
+                        let name = read_string(r)?;
                         let bricks = r.read_i32::<LittleEndian>()? as u32;
                         Ok(BrickOwner { name, id, bricks })
                     }
                     _ => {
                         let id = r.read_uuid()?;
-                        let name = r.read_string()?;
+                        let name = read_string(r)?;
                         Ok(BrickOwner::from(User { name, id }))
                     }
                 }
@@ -200,7 +297,16 @@ impl<R: Read> SaveReader<R> {
         // version >= 9: an array of strings
         //         else: not provided
         let physical_materials = match self.version {
-            _ if self.version >= 9 => cursor.read_array(|r| r.read_string())?,
+            _ if self.version >= 9 => cursor.read_array(read_string)?,
+            _ => vec![],
+        };
+
+        // match texture assets
+        // version >= 11: an array of strings, if there is any data left
+        //          else: not provided
+        let has_more = cursor.position() < cursor.get_ref().len() as u64;
+        let texture_assets = match self.version {
+            _ if self.version >= 11 && has_more => cursor.read_array(read_string)?,
             _ => vec![],
         };
 
@@ -212,6 +318,7 @@ impl<R: Read> SaveReader<R> {
             materials,
             brick_owners,
             physical_materials,
+            texture_assets,
         })
     }
 
@@ -238,19 +345,29 @@ impl<R: Read> SaveReader<R> {
             return Err(ReadError::BadSectionReadOrder);
         }
 
-        if self.version < 8 {
-            return Ok(());
-        }
-
-        if self.reader.read_u8()? != 0 {
-            let len = self.reader.read_i32::<LittleEndian>()?;
-            io::copy(&mut self.reader.by_ref().take(len as u64), &mut io::sink())?;
+        if let Some(len) = self.read_preview_len()? {
+            io::copy(&mut self.reader.by_ref().take(len), &mut io::sink())?;
         }
 
         self.preview_read = true;
         Ok(())
     }
 
+    /// Read the preview type and length, if there is a preview to skip.
+    fn read_preview_len(&mut self) -> Result<Option<u64>, ReadError> {
+        if self.version < 8 || self.reader.read_u8()? == 0 {
+            return Ok(None);
+        }
+
+        let len = self.reader.read_i32::<LittleEndian>()?;
+        if len < 0 {
+            return Err(
+                io::Error::new(io::ErrorKind::InvalidData, "invalid preview length").into(),
+            );
+        }
+        Ok(Some(len as u64))
+    }
+
     /// Read the bricks and components from a save.
     pub fn read_bricks(
         &mut self,
@@ -267,8 +384,11 @@ impl<R: Read> SaveReader<R> {
         let brick_asset_count = cmp::max(header2.brick_assets.len(), 2);
         let material_count = cmp::max(header2.materials.len(), 2);
         let physical_material_count = cmp::max(header2.physical_materials.len(), 2);
+        let color_count = cmp::max(header2.colors.len(), 2);
 
-        let inital_bricks_capacity = cmp::min(header1.brick_count as usize, 10_000_000);
+        // the brick count comes from the save, so don't trust it blindly
+        let brick_count = cmp::min(header1.brick_count as usize, MAX_BRICK_COUNT);
+        let inital_bricks_capacity = cmp::min(brick_count, 10_000_000);
         let mut bricks = Vec::with_capacity(inital_bricks_capacity);
         let mut components = HashMap::new();
 
@@ -276,9 +396,7 @@ impl<R: Read> SaveReader<R> {
         loop {
             // align and break out of the loop if we've seeked far enough ahead
             bits.byte_align();
-            if bricks.len() >= header1.brick_count as usize
-                || bits.reader().unwrap().position() >= len as u64
-            {
+            if bricks.len() >= brick_count || bits.reader().unwrap().position() >= len as u64 {
                 break;
             }
 
@@ -349,7 +467,7 @@ impl<R: Read> SaveReader<R> {
                         BrickColor::Unique(Color::from_bytes_bgra(bytes))
                     }
                 },
-                false => BrickColor::Index(bits.read_uint(header2.colors.len() as u32)?),
+                false => BrickColor::Index(bits.read_uint(color_count as u32)?),
             };
 
             let owner_index = if self.version >= 3 {
@@ -384,12 +502,18 @@ impl<R: Read> SaveReader<R> {
         if self.version >= 8 {
             let (mut cursor, _) = read_compressed(&mut self.reader)?;
             let len = cursor.read_i32::<LittleEndian>()?;
+            let lenient = self.lenient_strings;
+            let read_string = string_reader(lenient);
+            let read_bit_string = bit_string_reader(lenient);
 
             for _ in 0..len {
-                let name = cursor.read_string()?;
+                let name = read_string(&mut cursor)?;
 
-                let mut bit_bytes = vec![0u8; cursor.read_i32::<LittleEndian>()? as usize];
-                cursor.read_exact(&mut bit_bytes)?;
+                let bit_len = cursor.read_i32::<LittleEndian>()?;
+                if bit_len < 0 {
+                    return Err(ReadError::InvalidComponentData);
+                }
+                let bit_bytes = cursor.read_byte_vec(bit_len as usize)?;
                 let mut bits =
                     BitReader::endian(Cursor::new(bit_bytes), bitstream_io::LittleEndian);
 
@@ -397,15 +521,19 @@ impl<R: Read> SaveReader<R> {
                 let brick_indices = bits.read_array(|r| r.read_uint(brick_count as u32))?;
 
                 let properties = bits
-                    .read_array(|r| Ok((r.read_string()?, r.read_string()?)))?
+                    .read_array(|r| Ok((read_bit_string(r)?, read_bit_string(r)?)))?
                     .into_iter()
                     .collect::<Vec<_>>();
 
                 // components for each brick
                 for &i in brick_indices.iter() {
+                    if i as usize >= bricks.len() {
+                        return Err(ReadError::InvalidComponentData);
+                    }
+
                     let mut props = HashMap::new();
                     for (n, ty) in properties.iter() {
-                        props.insert(n.to_owned(), bits.read_unreal_type(ty)?);
+                        props.insert(n.to_owned(), bits.read_unreal_type(ty, lenient)?);
                     }
                     bricks[i as usize].components.insert(name.to_owned(), props);
                 }
@@ -461,49 +589,110 @@ impl<R: Read> SaveReader<R> {
     }
 }
 
-/// Read a compressed section from a `Read`, following the BRS spec for compressed sections.
-fn read_compressed(reader: &mut impl Read) -> Result<(Cursor<Vec<u8>>, i32), ReadError> {
-    let (uncompressed_size, compressed_size) = (
-        reader.read_i32::<LittleEndian>()?,
-        reader.read_i32::<LittleEndian>()?,
-    );
-    if uncompressed_size < 0 || compressed_size < 0 || compressed_size > uncompressed_size {
-        return Err(ReadError::InvalidCompression);
+impl<R: Read + Seek> SaveReader<R> {
+    /// Skip the first header by seeking past it.
+    pub fn seek_skip_header1(&mut self) -> Result<(), ReadError> {
+        self.seek_skip_section()?;
+        self.header1_read = true;
+        Ok(())
     }
 
-    let mut bytes = vec![0u8; uncompressed_size as usize];
+    /// Skip the second header by seeking past it.
+    pub fn seek_skip_header2(&mut self) -> Result<(), ReadError> {
+        self.seek_skip_section()?;
+        self.header2_read = true;
+        Ok(())
+    }
+
+    /// Skip over the preview section by seeking past it.
+    pub fn seek_skip_preview(&mut self) -> Result<(), ReadError> {
+        if !self.header2_read {
+            return Err(ReadError::BadSectionReadOrder);
+        }
+
+        if let Some(len) = self.read_preview_len()? {
+            self.reader.seek(SeekFrom::Current(len as i64))?;
+        }
+
+        self.preview_read = true;
+        Ok(())
+    }
 
+    /// Seek past a compressed section without reading its contents.
+    fn seek_skip_section(&mut self) -> Result<(), ReadError> {
+        let len = read_section_len(&mut self.reader)?;
+        self.reader.seek(SeekFrom::Current(len as i64))?;
+        Ok(())
+    }
+}
+
+/// Read a compressed section from a `Read`, following the BRS spec for compressed sections.
+fn read_compressed(reader: &mut impl Read) -> Result<(Cursor<Vec<u8>>, i32), ReadError> {
+    let (uncompressed_size, compressed_size) = read_section_sizes(reader)?;
+
+    // sizes come from untrusted data, so avoid allocating
+    // more than the section actually holds up front
     if compressed_size == 0 {
         // no need to decompress first
-        reader.read_exact(&mut bytes)?;
-    } else {
-        // decompress first, then read
-        let mut compressed = vec![0u8; compressed_size as usize];
-        reader.read_exact(&mut compressed)?;
-        ZlibDecoder::new(&compressed[..]).read_exact(&mut bytes)?;
+        let bytes = reader.read_byte_vec(uncompressed_size as usize)?;
+        return Ok((Cursor::new(bytes), uncompressed_size));
+    }
+
+    // decompress first, then read
+    let compressed = reader.read_byte_vec(compressed_size.unsigned_abs() as usize)?;
+
+    #[cfg(feature = "lz4")]
+    if compressed_size < 0 {
+        // lz4 can't expand data by more than 255 times
+        if uncompressed_size as usize > compressed.len() * 255 + 16 {
+            return Err(ReadError::InvalidCompression);
+        }
+        let mut bytes = vec![0u8; uncompressed_size as usize];
+        let len = lz4_flex::block::decompress_into(&compressed[..], &mut bytes)
+            .map_err(|_| ReadError::InvalidCompression)?;
+        if len != bytes.len() {
+            return Err(ReadError::InvalidCompression);
+        }
+        return Ok((Cursor::new(bytes), uncompressed_size));
     }
 
+    let bytes = ZlibDecoder::new(&compressed[..]).read_byte_vec(uncompressed_size as usize)?;
     Ok((Cursor::new(bytes), uncompressed_size))
 }
 
 /// Read a compressed section from a `Read`, discarding its contents.
 fn skip_compressed(reader: &mut impl Read) -> Result<(), ReadError> {
+    let len = read_section_len(reader)?;
+    io::copy(&mut reader.take(len), &mut io::sink())?;
+    Ok(())
+}
+
+/// Read the sizes preceding a compressed section, returning the length of its data.
+fn read_section_len(reader: &mut impl Read) -> Result<u64, ReadError> {
+    let (uncompressed_size, compressed_size) = read_section_sizes(reader)?;
+    Ok(if compressed_size == 0 {
+        uncompressed_size as u64
+    } else {
+        compressed_size.unsigned_abs() as u64
+    })
+}
+
+/// Read and validate the (uncompressed, compressed) sizes preceding a compressed section.
+///
+/// A negative compressed size marks a non-standard lz4 section, which is only
+/// accepted with the `lz4` feature.
+fn read_section_sizes(reader: &mut impl Read) -> Result<(i32, i32), ReadError> {
     let (uncompressed_size, compressed_size) = (
         reader.read_i32::<LittleEndian>()?,
         reader.read_i32::<LittleEndian>()?,
     );
-    if uncompressed_size < 0 || compressed_size < 0 || compressed_size > uncompressed_size {
+    if uncompressed_size < 0
+        || (compressed_size < 0 && !cfg!(feature = "lz4"))
+        || compressed_size == i32::MIN
+        || compressed_size.abs() > uncompressed_size
+    {
         return Err(ReadError::InvalidCompression);
     }
 
-    io::copy(
-        &mut reader.take(if compressed_size == 0 {
-            uncompressed_size as u64
-        } else {
-            compressed_size as u64
-        }),
-        &mut io::sink(),
-    )?;
-
-    Ok(())
+    Ok((uncompressed_size, compressed_size))
 }
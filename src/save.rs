@@ -1,11 +1,16 @@
 //! General save file types and helpers.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::io::Read;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::FromStr;
 
 use byteorder::{LittleEndian, ReadBytesExt};
+use chrono::TimeZone;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+use thiserror::Error;
 
 pub use chrono::{DateTime, Utc};
 pub use uuid::Uuid;
@@ -18,11 +23,12 @@ use {
         Deserialize, Deserializer, Serialize, Serializer,
     },
     serde_repr::{Deserialize_repr, Serialize_repr},
-    std::fmt,
+    std::borrow::Cow,
 };
 
+use crate::ext::ReadExt;
 use crate::read::ReadError;
-use crate::SAVE_VERSION;
+use crate::{MAGIC_BYTES, SAVE_VERSION};
 
 /// An entire save file.
 ///
@@ -31,42 +37,2658 @@ use crate::SAVE_VERSION;
 ///
 /// [`SaveWriter`]: crate::write::SaveWriter
 /// [`SaveReader`]: crate::read::SaveReader
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize), serde(default))]
+pub struct SaveData {
+    /// The version of the save. Only relevant for reads; this automatically uses [`SAVE_VERSION`](crate::SAVE_VERSION) when writing.
+    pub version: u16,
+
+    /// The game version the save was saved on.
+    pub game_version: i32,
+
+    /// The first header of the save.
+    #[cfg_attr(feature = "serialize", serde(flatten))]
+    pub header1: Header1,
+
+    /// The second header of the save.
+    #[cfg_attr(feature = "serialize", serde(flatten))]
+    pub header2: Header2,
+
+    /// The preview of the save, if any.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    pub preview: Preview,
+
+    /// The bricks in the save.
+    pub bricks: Vec<Brick>,
+
+    /// The components in the save.
+    pub components: HashMap<String, Component>,
+}
+
+impl SaveData {
+    /// Convert this `SaveData` into a `SaveOctree` for quick traversal of bricks in space.
+    #[cfg(feature = "util")]
+    pub fn into_octree(self) -> crate::util::octree::SaveOctree {
+        crate::util::octree::SaveOctree::new(self)
+    }
+
+    /// Get a sorted list of the names of every component in the save.
+    pub fn all_component_names(&self) -> Vec<&str> {
+        let mut names = self
+            .components
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Iterate over every brick (and its index) that has the component `component_name`.
+    pub fn bricks_with_component<'a>(
+        &'a self,
+        component_name: &'a str,
+    ) -> impl Iterator<Item = (usize, &'a Brick)> + 'a {
+        self.bricks
+            .iter()
+            .enumerate()
+            .filter(move |(_, brick)| brick.components.contains_key(component_name))
+    }
+
+    /// Iterate over every brick (and its index) that has no components.
+    pub fn bricks_without_any_component(&self) -> impl Iterator<Item = (usize, &Brick)> {
+        self.bricks
+            .iter()
+            .enumerate()
+            .filter(|(_, brick)| brick.components.is_empty())
+    }
+
+    /// Count the bricks that have at least one component.
+    pub fn count_bricks_with_any_component(&self) -> usize {
+        self.bricks
+            .iter()
+            .filter(|brick| !brick.components.is_empty())
+            .count()
+    }
+
+    /// Count the bricks that have no components.
+    pub fn count_bricks_without_any_component(&self) -> usize {
+        self.bricks.len() - self.count_bricks_with_any_component()
+    }
+
+    /// The fraction of bricks, from 0 to 1, that have at least one component.
+    /// A save with no bricks has a coverage of 0.
+    pub fn component_coverage(&self) -> f64 {
+        if self.bricks.is_empty() {
+            return 0.0;
+        }
+        self.count_bricks_with_any_component() as f64 / self.bricks.len() as f64
+    }
+
+    /// Map each component in the save to the number of bricks it is applied to.
+    pub fn component_brick_counts(&self) -> HashMap<&str, usize> {
+        self.components
+            .iter()
+            .map(|(name, component)| (name.as_str(), component.brick_indices.len()))
+            .collect()
+    }
+
+    /// Whether or not the save has the component `name`.
+    #[inline]
+    pub fn has_component(&self, name: &str) -> bool {
+        self.components.contains_key(name)
+    }
+
+    /// Whether or not the save has any components.
+    #[inline]
+    pub fn has_any_component(&self) -> bool {
+        !self.components.is_empty()
+    }
+
+    /// Whether or not the brick at `brick_index` has the component `component_name`. Returns
+    /// `false` if there is no such brick.
+    pub fn brick_has_component(&self, brick_index: usize, component_name: &str) -> bool {
+        self.bricks
+            .get(brick_index)
+            .is_some_and(|brick| brick.components.contains_key(component_name))
+    }
+
+    /// Count the bricks the component `component_name` is applied to, according to its
+    /// `brick_indices`, or 0 if the save does not have it.
+    pub fn count_bricks_with_component(&self, component_name: &str) -> usize {
+        self.components
+            .get(component_name)
+            .map_or(0, |component| component.brick_indices.len())
+    }
+
+    /// Remove the component `component_name` from the save and from every brick, returning
+    /// whether or not the save had the component.
+    pub fn remove_component(&mut self, component_name: &str) -> bool {
+        let existed = self.components.remove(component_name).is_some();
+        for brick in self.bricks.iter_mut() {
+            brick.components.remove(component_name);
+        }
+        existed
+    }
+
+    /// Rename the component `old` to `new` in the save and on every brick, returning whether
+    /// or not it was renamed. Nothing is changed if the save has no `old` component or already
+    /// has a `new` component.
+    pub fn rename_component(&mut self, old: &str, new: &str) -> bool {
+        if old == new {
+            return self.components.contains_key(old);
+        }
+        if self.components.contains_key(new) {
+            return false;
+        }
+        let component = match self.components.remove(old) {
+            Some(component) => component,
+            None => return false,
+        };
+
+        self.components.insert(new.into(), component);
+        for brick in self.bricks.iter_mut() {
+            if let Some(props) = brick.components.remove(old) {
+                brick.components.insert(new.into(), props);
+            }
+        }
+        true
+    }
+
+    /// Move the component `component_name` from this save to `target`.
+    ///
+    /// `brick_index_map` maps brick indices in this save to brick indices in `target`. The
+    /// component's brick indices are remapped with it, along with the component's properties
+    /// on each brick; bricks missing from the map lose the component. Nothing is changed if
+    /// an error is returned.
+    pub fn transfer_component(
+        &mut self,
+        target: &mut SaveData,
+        component_name: &str,
+        brick_index_map: &HashMap<usize, usize>,
+    ) -> Result<(), ComponentTransferError> {
+        let component = self
+            .components
+            .get(component_name)
+            .ok_or_else(|| ComponentTransferError::MissingComponent(component_name.into()))?;
+        if target.components.contains_key(component_name) {
+            return Err(ComponentTransferError::ComponentExists(
+                component_name.into(),
+            ));
+        }
+        if let Some(&index) = component
+            .brick_indices
+            .iter()
+            .filter_map(|&i| brick_index_map.get(&(i as usize)))
+            .find(|&&i| i >= target.bricks.len())
+        {
+            return Err(ComponentTransferError::BrickOutOfRange(index));
+        }
+
+        let mut component = self.components.remove(component_name).unwrap();
+        for old in std::mem::take(&mut component.brick_indices) {
+            let new = match brick_index_map.get(&(old as usize)) {
+                Some(&new) => new,
+                None => continue,
+            };
+            component.brick_indices.push(new as u32);
+            if let Some(props) = self
+                .bricks
+                .get_mut(old as usize)
+                .and_then(|brick| brick.components.remove(component_name))
+            {
+                target.bricks[new]
+                    .components
+                    .insert(component_name.into(), props);
+            }
+        }
+
+        self.remove_component(component_name);
+        target.components.insert(component_name.into(), component);
+        Ok(())
+    }
+
+    /// Whether or not the save has no bricks.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bricks.is_empty()
+    }
+
+    /// The number of bricks in the save. Unlike `header1.brick_count`, this is never stale.
+    #[inline]
+    pub fn brick_count(&self) -> usize {
+        self.bricks.len()
+    }
+
+    /// Remove every brick, component, and brick owner from the save.
+    ///
+    /// Assets, materials, colors, and the rest of the headers are left as they are.
+    pub fn clear(&mut self) {
+        self.bricks.clear();
+        self.components.clear();
+        self.header1.brick_count = 0;
+        self.header2.brick_owners.clear();
+    }
+
+    /// Reset the save to [`SaveData::default`], keeping only its version and game version.
+    pub fn clear_all(&mut self) {
+        *self = SaveData {
+            version: self.version,
+            game_version: self.game_version,
+            ..Default::default()
+        };
+    }
+
+    /// Get the time passed since the save was made, or `None` if it has no save time.
+    ///
+    /// A save time of zero ticks (`0001-01-01`) is treated as no save time.
+    pub fn time_since_save(&self) -> Option<chrono::Duration> {
+        let save_time = self.header1.save_time?;
+        if save_time == Utc.with_ymd_and_hms(1, 1, 1, 0, 0, 0).unwrap() {
+            return None;
+        }
+        Some(Utc::now() - save_time)
+    }
+
+    /// Whether or not the save was made at most `duration` ago. Saves without a save time
+    /// never are.
+    pub fn was_saved_within(&self, duration: chrono::Duration) -> bool {
+        self.time_since_save().is_some_and(|age| age <= duration)
+    }
+
+    /// Format the [`time_since_save`](SaveData::time_since_save) in its largest whole unit,
+    /// such as `3 days ago` or `1 hour ago`.
+    pub fn save_age_human_readable(&self) -> Option<String> {
+        let age = self.time_since_save()?;
+        let (amount, unit) = match age {
+            _ if age.num_days() >= 365 => (age.num_days() / 365, "year"),
+            _ if age.num_days() >= 1 => (age.num_days(), "day"),
+            _ if age.num_hours() >= 1 => (age.num_hours(), "hour"),
+            _ if age.num_minutes() >= 1 => (age.num_minutes(), "minute"),
+            _ if age.num_seconds() >= 0 => return Some("just now".into()),
+            _ => return Some("in the future".into()),
+        };
+        let plural = if amount == 1 { "" } else { "s" };
+        Some(format!("{} {}{} ago", amount, unit, plural))
+    }
+
+    /// Set `header1.brick_count` to the number of bricks in the save.
+    pub fn repair_brick_count(&mut self) {
+        self.header1.brick_count = self.bricks.len() as u32;
+    }
+
+    /// Fix out of range indices on bricks and components.
+    ///
+    /// Brick asset, material, physical material, and color indices are clamped to the
+    /// last entry of their respective lists in `header2`. Component brick indices past the
+    /// end of the brick list are dropped, and components on bricks that are not listed in
+    /// their component's `brick_indices` are removed from the brick.
+    pub fn repair_indices(&mut self) -> RepairReport {
+        let mut report = RepairReport::default();
+
+        let max_asset = self.header2.brick_assets.len().saturating_sub(1) as u32;
+        let max_material = self.header2.materials.len().saturating_sub(1) as u32;
+        let max_physical = self.header2.physical_materials.len().saturating_sub(1) as u32;
+        let max_color = self.header2.colors.len().saturating_sub(1) as u32;
+
+        for (i, brick) in self.bricks.iter_mut().enumerate() {
+            if brick.asset_name_index > max_asset {
+                report.changes.push(Repair::AssetIndex {
+                    brick: i,
+                    from: brick.asset_name_index,
+                    to: max_asset,
+                });
+                brick.asset_name_index = max_asset;
+            }
+
+            if brick.material_index > max_material {
+                report.changes.push(Repair::MaterialIndex {
+                    brick: i,
+                    from: brick.material_index,
+                    to: max_material,
+                });
+                brick.material_index = max_material;
+            }
+
+            if brick.physical_index > max_physical {
+                report.changes.push(Repair::PhysicalIndex {
+                    brick: i,
+                    from: brick.physical_index,
+                    to: max_physical,
+                });
+                brick.physical_index = max_physical;
+            }
+
+            if let BrickColor::Index(index) = brick.color {
+                if index > max_color {
+                    report.changes.push(Repair::ColorIndex {
+                        brick: i,
+                        from: index,
+                        to: max_color,
+                    });
+                    brick.color = BrickColor::Index(max_color);
+                }
+            }
+        }
+
+        let brick_count = self.bricks.len();
+        for (name, component) in self.components.iter_mut() {
+            component.brick_indices.retain(|&index| {
+                let valid = (index as usize) < brick_count;
+                if !valid {
+                    report.changes.push(Repair::ComponentBrickIndex {
+                        component: name.to_owned(),
+                        index,
+                    });
+                }
+                valid
+            });
+        }
+
+        let listed_bricks = self
+            .components
+            .iter()
+            .map(|(name, component)| {
+                let indices = component.brick_indices.iter().copied();
+                (name.as_str(), indices.collect::<HashSet<u32>>())
+            })
+            .collect::<HashMap<_, _>>();
+        for (i, brick) in self.bricks.iter_mut().enumerate() {
+            brick.components.retain(|name, _| {
+                let listed = listed_bricks
+                    .get(name.as_str())
+                    .is_some_and(|indices| indices.contains(&(i as u32)));
+                if !listed {
+                    report.changes.push(Repair::BrickComponent {
+                        brick: i,
+                        component: name.to_owned(),
+                    });
+                }
+                listed
+            });
+        }
+
+        report
+    }
+
+    /// Remove every invisible brick, returning how many were removed.
+    pub fn remove_invisible_bricks(&mut self) -> usize {
+        let before = self.bricks.len();
+        self.filter_bricks(|brick| brick.visibility);
+        before - self.bricks.len()
+    }
+
+    /// Remove every procedural brick with a size of zero, returning how many were removed.
+    pub fn remove_zero_size_bricks(&mut self) -> usize {
+        let before = self.bricks.len();
+        self.filter_bricks(|brick| brick.size != Size::Procedural(0, 0, 0));
+        before - self.bricks.len()
+    }
+
+    /// Remove invisible and zero size bricks, then [`compact`](SaveData::compact) the save.
+    pub fn cleanup(&mut self) -> CleanupReport {
+        CleanupReport {
+            invisible_bricks: self.remove_invisible_bricks(),
+            zero_size_bricks: self.remove_zero_size_bricks(),
+            compact: self.compact(),
+        }
+    }
+
+    /// Remove unused entries from the save: components applied to no bricks, and colors,
+    /// materials, and physical materials in `header2` that no brick refers to.
+    ///
+    /// Brick indices into the compacted lists are remapped to match.
+    pub fn compact(&mut self) -> CompactReport {
+        let mut report = CompactReport::default();
+
+        let before = self.components.len();
+        let bricks = &self.bricks;
+        self.components.retain(|name, component| {
+            !component.brick_indices.is_empty()
+                || bricks
+                    .iter()
+                    .any(|brick| brick.components.contains_key(name))
+        });
+        report.components = before - self.components.len();
+
+        let mut used_colors = vec![false; self.header2.colors.len()];
+        let mut used_materials = vec![false; self.header2.materials.len()];
+        let mut used_physical = vec![false; self.header2.physical_materials.len()];
+        for brick in self.bricks.iter() {
+            if let BrickColor::Index(index) = brick.color {
+                mark_used(&mut used_colors, index);
+            }
+            mark_used(&mut used_materials, brick.material_index);
+            mark_used(&mut used_physical, brick.physical_index);
+        }
+
+        let colors = compact_entries(&mut self.header2.colors, &used_colors);
+        let materials = compact_entries(&mut self.header2.materials, &used_materials);
+        let physical = compact_entries(&mut self.header2.physical_materials, &used_physical);
+        report.colors = used_colors.len() - self.header2.colors.len();
+        report.materials = used_materials.len() - self.header2.materials.len();
+        report.physical_materials = used_physical.len() - self.header2.physical_materials.len();
+
+        for brick in self.bricks.iter_mut() {
+            if let BrickColor::Index(index) = &mut brick.color {
+                *index = remap_entry(&colors, *index);
+            }
+            brick.material_index = remap_entry(&materials, brick.material_index);
+            brick.physical_index = remap_entry(&physical, brick.physical_index);
+        }
+
+        report
+    }
+
+    /// Remove duplicate colors from `header2`, keeping the first of each, and remap brick
+    /// color indices to match. Returns the number of colors removed.
+    pub fn normalize_colors(&mut self) -> usize {
+        let old = std::mem::take(&mut self.header2.colors);
+        let before = old.len();
+        let mut seen = HashMap::new();
+        let mut remap = Vec::with_capacity(before);
+        for color in old {
+            match seen.get(&color) {
+                Some(&index) => remap.push(index),
+                None => {
+                    let index = self.header2.colors.len() as u32;
+                    seen.insert(color.clone(), index);
+                    remap.push(index);
+                    self.header2.colors.push(color);
+                }
+            }
+        }
+
+        for brick in self.bricks.iter_mut() {
+            if let BrickColor::Index(index) = &mut brick.color {
+                *index = remap_entry(&remap, *index);
+            }
+        }
+        before - self.header2.colors.len()
+    }
+
+    /// Recount the number of bricks owned by each brick owner in `header2`.
+    pub fn normalize_owners(&mut self) {
+        let mut counts = vec![0u32; self.header2.brick_owners.len()];
+        for brick in self.bricks.iter() {
+            if let Some(count) = (brick.owner_index as usize)
+                .checked_sub(1)
+                .and_then(|i| counts.get_mut(i))
+            {
+                *count += 1;
+            }
+        }
+
+        for (owner, count) in self.header2.brick_owners.iter_mut().zip(counts) {
+            owner.bricks = count;
+        }
+    }
+
+    /// Rename the brick owner with the id `id`, returning whether or not it was found.
+    pub fn rename_owner(&mut self, id: Uuid, new_name: impl Into<String>) -> bool {
+        match self.header2.brick_owners.iter_mut().find(|o| o.id == id) {
+            Some(owner) => {
+                owner.name = new_name.into();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Rename the brick owner at the 1-based owner index `index`, returning whether or not
+    /// it exists.
+    pub fn rename_owner_at_index(&mut self, index: u32, new_name: impl Into<String>) -> bool {
+        match (index as usize)
+            .checked_sub(1)
+            .and_then(|i| self.header2.brick_owners.get_mut(i))
+        {
+            Some(owner) => {
+                owner.name = new_name.into();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Combine brick owners that share an id into the first of them, summing their brick
+    /// counts and keeping the first name encountered.
+    pub fn merge_owners(&mut self) {
+        self.merge_owners_with_resolution(|first, _| first.clone());
+    }
+
+    /// Like [`merge_owners`](SaveData::merge_owners), but the name of each combined owner is
+    /// taken from `resolve(kept, duplicate)`. Brick counts are always summed.
+    pub fn merge_owners_with_resolution<F: Fn(&BrickOwner, &BrickOwner) -> BrickOwner>(
+        &mut self,
+        resolve: F,
+    ) {
+        let mut merged: Vec<BrickOwner> = Vec::with_capacity(self.header2.brick_owners.len());
+        let mut positions: HashMap<Uuid, usize> = HashMap::new();
+        let mut mapping = Vec::with_capacity(self.header2.brick_owners.len());
+
+        for owner in self.header2.brick_owners.drain(..) {
+            match positions.get(&owner.id) {
+                Some(&i) => {
+                    let kept = &mut merged[i];
+                    kept.name = resolve(kept, &owner).name;
+                    kept.bricks = kept.bricks.saturating_add(owner.bricks);
+                    mapping.push(i as u32);
+                }
+                None => {
+                    positions.insert(owner.id, merged.len());
+                    mapping.push(merged.len() as u32);
+                    merged.push(owner);
+                }
+            }
+        }
+        self.header2.brick_owners = merged;
+
+        for brick in self.bricks.iter_mut() {
+            if let Some(&i) = (brick.owner_index as usize)
+                .checked_sub(1)
+                .and_then(|i| mapping.get(i))
+            {
+                brick.owner_index = i + 1;
+            }
+        }
+    }
+
+    /// Get the indices of every brick owned by the brick owner with the id `id`.
+    ///
+    /// Public bricks are never included; see [`public_brick_indices`](SaveData::public_brick_indices).
+    pub fn brick_indices_for_owner(&self, id: Uuid) -> Vec<usize> {
+        let owner_index = match self.header2.brick_owners.iter().position(|o| o.id == id) {
+            Some(i) => i as u32 + 1,
+            None => return vec![],
+        };
+
+        self.bricks
+            .iter()
+            .enumerate()
+            .filter(|(_, brick)| brick.owner_index == owner_index)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Get the indices of every public brick (with an owner index of 0).
+    pub fn public_brick_indices(&self) -> Vec<usize> {
+        self.bricks
+            .iter()
+            .enumerate()
+            .filter(|(_, brick)| brick.owner_index == 0)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Get every distinct owner index used by a brick in the save, including 0 (public).
+    pub fn collect_owner_indices(&self) -> HashSet<u32> {
+        self.bricks.iter().map(|brick| brick.owner_index).collect()
+    }
+
+    /// Rebuild `header2.brick_owners` from the owner indices used by the bricks in the save.
+    ///
+    /// Each owner index is looked up in `user_lookup`. Owners that are missing from it, as
+    /// well as gaps between used owner indices, are filled with [`User::default`]. Brick
+    /// owner indices are left unchanged.
+    pub fn auto_owner_from_bricks(&mut self, user_lookup: &HashMap<u32, User>) {
+        let max_index = self
+            .bricks
+            .iter()
+            .map(|brick| brick.owner_index)
+            .max()
+            .unwrap_or(0);
+
+        self.header2.brick_owners = (1..=max_index)
+            .map(|index| user_lookup.get(&index).cloned().unwrap_or_default().into())
+            .collect();
+        self.normalize_owners();
+    }
+
+    /// Remove every brick for which `f` returns `false`.
+    ///
+    /// Component brick indices, the brick count, and brick owner counts are updated to match.
+    pub fn filter_bricks<F: FnMut(&Brick) -> bool>(&mut self, mut f: F) {
+        let keep = self.bricks.iter().map(&mut f).collect::<Vec<_>>();
+        self.keep_bricks(&keep);
+    }
+
+    /// Keep only the bricks for which `f` returns `true`. Equivalent to [`filter_bricks`](SaveData::filter_bricks).
+    pub fn retain_bricks<F: FnMut(&Brick) -> bool>(&mut self, f: F) {
+        self.filter_bricks(f);
+    }
+
+    /// Create a save with the headers and preview of this one, and copies of the bricks at
+    /// `indices`, in that order.
+    ///
+    /// Component brick indices are renumbered to match, the brick count and brick owner
+    /// counts are recomputed, and the save is otherwise left as-is. Panics if any index is
+    /// out of range.
+    pub fn clone_bricks(&self, indices: &[usize]) -> SaveData {
+        let mut old_to_new: HashMap<u32, Vec<u32>> = HashMap::new();
+        for (new, &old) in indices.iter().enumerate() {
+            old_to_new.entry(old as u32).or_default().push(new as u32);
+        }
+
+        let mut data = SaveData::from_template(self);
+        data.bricks = indices.iter().map(|&i| self.bricks[i].clone()).collect();
+        data.components = self
+            .components
+            .iter()
+            .map(|(name, component)| {
+                let mut brick_indices = component
+                    .brick_indices
+                    .iter()
+                    .filter_map(|old| old_to_new.get(old))
+                    .flatten()
+                    .copied()
+                    .collect::<Vec<_>>();
+                brick_indices.sort_unstable();
+                let component = Component {
+                    brick_indices,
+                    ..component.clone()
+                };
+                (name.clone(), component)
+            })
+            .collect();
+
+        data.repair_brick_count();
+        data.normalize_owners();
+        data
+    }
+
+    /// Replace every brick with the result of passing it through `f`.
+    ///
+    /// Bricks keep their positions in the list, so component brick indices are left as-is.
+    pub fn map_bricks<F: Fn(Brick) -> Brick>(&mut self, f: F) {
+        self.bricks = self.bricks.drain(..).map(f).collect();
+        self.repair_brick_count();
+    }
+
+    /// Like [`map_bricks`](SaveData::map_bricks), but also passes each brick's index to `f`.
+    pub fn map_bricks_indexed<F: Fn(usize, Brick) -> Brick>(&mut self, f: F) {
+        self.bricks = self
+            .bricks
+            .drain(..)
+            .enumerate()
+            .map(|(i, brick)| f(i, brick))
+            .collect();
+        self.repair_brick_count();
+    }
+
+    /// Set the material of every brick to `material_name`, adding it to `header2` if needed.
+    ///
+    /// Materials that are no longer used are left in `header2`; use
+    /// [`compact`](SaveData::compact) to remove them.
+    pub fn set_material_for_all(&mut self, material_name: &str) -> Result<(), MaterialError> {
+        let material_index = self.header2.add_material(material_name)?;
+        for brick in self.bricks.iter_mut() {
+            brick.material_index = material_index;
+        }
+        Ok(())
+    }
+
+    /// Set the material of every brick with the asset `asset_name` to `material_name`, adding
+    /// it to `header2` if needed. See [`set_material_for_all`](SaveData::set_material_for_all).
+    pub fn set_material_for_asset(
+        &mut self,
+        asset_name: &str,
+        material_name: &str,
+    ) -> Result<(), MaterialError> {
+        let material_index = self.header2.add_material(material_name)?;
+        let asset_index = match self
+            .header2
+            .brick_assets
+            .iter()
+            .position(|a| a == asset_name)
+        {
+            Some(i) => i as u32,
+            None => return Ok(()),
+        };
+
+        for brick in self.bricks.iter_mut() {
+            if brick.asset_name_index == asset_index {
+                brick.material_index = material_index;
+            }
+        }
+        Ok(())
+    }
+
+    /// Toggle the visibility of every brick.
+    pub fn invert_visibility(&mut self) {
+        self.map_bricks(|brick| Brick {
+            visibility: !brick.visibility,
+            ..brick
+        });
+    }
+
+    /// Set the visibility of every brick to `visible`.
+    pub fn set_visibility_for_all(&mut self, visible: bool) {
+        self.map_bricks(|brick| Brick {
+            visibility: visible,
+            ..brick
+        });
+    }
+
+    /// Set the visibility of every brick owned by the brick owner with the id `owner_id`.
+    pub fn set_visibility_for_owner(&mut self, owner_id: Uuid, visible: bool) {
+        let owner_index = match self
+            .header2
+            .brick_owners
+            .iter()
+            .position(|o| o.id == owner_id)
+        {
+            Some(i) => i as u32 + 1,
+            None => return,
+        };
+
+        self.map_bricks(|brick| Brick {
+            visibility: if brick.owner_index == owner_index {
+                visible
+            } else {
+                brick.visibility
+            },
+            ..brick
+        });
+    }
+
+    /// Remove every brick whose entry in `keep` is `false`, fixing up component brick
+    /// indices, the brick count, and brick owner counts.
+    fn keep_bricks(&mut self, keep: &[bool]) {
+        let mut old_to_new = Vec::with_capacity(keep.len());
+        let mut next = 0u32;
+        for &kept in keep.iter() {
+            old_to_new.push(if kept {
+                next += 1;
+                Some(next - 1)
+            } else {
+                None
+            });
+        }
+
+        let mut i = 0;
+        self.bricks.retain(|_| {
+            i += 1;
+            keep.get(i - 1).copied().unwrap_or(true)
+        });
+
+        for component in self.components.values_mut() {
+            component.remap_brick_indices(&old_to_new);
+        }
+
+        self.repair_brick_count();
+        self.normalize_owners();
+    }
+
+    /// Remove bricks that are buried inside the build, keeping `shell_thickness` layers of
+    /// bricks below the surface. A `shell_thickness` of 0 is treated as 1.
+    ///
+    /// See [`SaveOctree::interior_bricks`](crate::util::octree::SaveOctree::interior_bricks)
+    /// for what is considered interior.
+    #[cfg(feature = "util")]
+    pub fn hollow(&mut self, shell_thickness: u32) {
+        let octree = std::mem::take(self).into_octree();
+        let interior = octree.interior_bricks(shell_thickness);
+        *self = octree.into_inner();
+
+        let mut keep = vec![true; self.bricks.len()];
+        for i in interior {
+            keep[i] = false;
+        }
+        self.keep_bricks(&keep);
+    }
+
+    /// Fill the space below every brick with nothing directly under it down to Z = 0.
+    /// See [`fill_below_to`](SaveData::fill_below_to).
+    #[cfg(feature = "util")]
+    pub fn fill_below(&mut self, fill_asset_index: u32, fill_material_index: u32) {
+        self.fill_below_to(fill_asset_index, fill_material_index, 0);
+    }
+
+    /// Fill the space below every brick with nothing directly under it with a column of
+    /// plate-height bricks, down to `z_floor` or the top of the next brick below it.
+    ///
+    /// Fill bricks use the procedural asset `fill_asset_index` and the material
+    /// `fill_material_index`, cover the footprint of the brick above them, and take its color
+    /// and owner. The bottom brick of a column is shortened to fit, and a 1 unit gap that
+    /// can't be filled is left at the bottom.
+    ///
+    /// Each column stops at the top of the highest brick under its footprint, so columns
+    /// only overlap each other where the bricks above them already overlap.
+    #[cfg(feature = "util")]
+    pub fn fill_below_to(&mut self, fill_asset_index: u32, fill_material_index: u32, z_floor: i32) {
+        const PLATE_Z: i32 = 4;
+
+        let octree = std::mem::take(self).into_octree();
+        let mut fill = vec![];
+        for brick in octree.data().bricks.iter() {
+            let floor = match unsupported_floor(&octree, brick, z_floor) {
+                Some(floor) => floor,
+                None => continue,
+            };
+
+            let size = octree.brick_size(brick);
+            let mut top = octree.brick_bounds(brick).0 .2;
+            while top - floor >= 2 {
+                let height = std::cmp::min(PLATE_Z, (top - floor) / 2 * 2);
+                fill.push(Brick {
+                    asset_name_index: fill_asset_index,
+                    size: Size::Procedural(size.0, size.1, height as u32 / 2),
+                    position: (brick.position.0, brick.position.1, top - height / 2),
+                    material_index: fill_material_index,
+                    color: brick.color.clone(),
+                    owner_index: brick.owner_index,
+                    ..Default::default()
+                });
+                top -= height;
+            }
+        }
+
+        *self = octree.into_inner();
+        self.bricks.extend(fill);
+        self.repair_brick_count();
+        self.normalize_owners();
+    }
+
+    /// Get the index and bottom face center of every brick above Z = 0 with nothing directly
+    /// under it.
+    #[cfg(feature = "util")]
+    pub fn overhang_regions(&self) -> Vec<(usize, (i32, i32, i32))> {
+        overhangs(&self.clone().into_octree())
+    }
+
+    /// Add a pillar under every brick above Z = 0 with nothing directly under it, reaching
+    /// down to Z = 0 or the top of the next brick below it.
+    ///
+    /// Bricks with a footprint smaller than `min_overhang_area` square units are skipped.
+    /// Pillars use the procedural asset `support_asset_index` and the material
+    /// `support_material_index`, are at most 1 stud wide, and take the color and owner of
+    /// the brick they support.
+    #[cfg(feature = "util")]
+    pub fn generate_supports(
+        &mut self,
+        support_asset_index: u32,
+        support_material_index: u32,
+        min_overhang_area: u32,
+    ) {
+        const STUD_HALF_XY: u32 = 5;
+
+        let octree = std::mem::take(self).into_octree();
+        let mut supports = vec![];
+        for (i, (x, y, bottom)) in overhangs(&octree) {
+            let brick = &octree.data().bricks[i];
+            let size = octree.brick_size(brick);
+            if (size.0 as u64 * 2) * (size.1 as u64 * 2) < min_overhang_area as u64 {
+                continue;
+            }
+
+            let floor = unsupported_floor(&octree, brick, 0).unwrap_or(0);
+            let height = (bottom - floor) / 2 * 2;
+            if height < 2 {
+                continue;
+            }
+
+            supports.push(Brick {
+                asset_name_index: support_asset_index,
+                size: Size::Procedural(
+                    size.0.min(STUD_HALF_XY),
+                    size.1.min(STUD_HALF_XY),
+                    height as u32 / 2,
+                ),
+                position: (x, y, bottom - height / 2),
+                material_index: support_material_index,
+                color: brick.color.clone(),
+                owner_index: brick.owner_index,
+                ..Default::default()
+            });
+        }
+
+        *self = octree.into_inner();
+        self.bricks.extend(supports);
+        self.repair_brick_count();
+        self.normalize_owners();
+    }
+
+    /// Count the bricks that [`hollow(1)`](SaveData::hollow) would remove, without removing them.
+    #[cfg(feature = "util")]
+    pub fn count_interior_bricks(&self) -> usize {
+        self.clone().into_octree().interior_bricks(1).len()
+    }
+
+    /// Generate the bricks making up an axis-aligned box from `min` to `max`.
+    ///
+    /// The box is laid out on the plate grid (10 units in X and Y, 4 units in Z), so `min` and
+    /// `max` should lie on it. Each region of the box is filled with a single procedural brick:
+    /// a solid box is one brick, a shell is at most 6, and a wireframe is at most 12.
+    pub fn generate_box(
+        min: (i32, i32, i32),
+        max: (i32, i32, i32),
+        fill: BoxFill,
+        brick_asset_index: u32,
+        color: BrickColor,
+    ) -> Vec<Brick> {
+        const PLATE_XY: i32 = 10;
+        const PLATE_Z: i32 = 4;
+
+        if min.0 >= max.0 || min.1 >= max.1 || min.2 >= max.2 {
+            return vec![];
+        }
+
+        // split an axis into its boundary and interior segments, as (start, end, is_boundary)
+        fn segments(lo: i32, hi: i32, cell: i32) -> Vec<(i32, i32, bool)> {
+            if hi - lo <= cell * 2 {
+                vec![(lo, hi, true)]
+            } else {
+                vec![
+                    (lo, lo + cell, true),
+                    (lo + cell, hi - cell, false),
+                    (hi - cell, hi, true),
+                ]
+            }
+        }
+
+        let region = |x: (i32, i32, bool), y: (i32, i32, bool), z: (i32, i32, bool)| Brick {
+            asset_name_index: brick_asset_index,
+            size: Size::Procedural(
+                ((x.1 - x.0) / 2) as u32,
+                ((y.1 - y.0) / 2) as u32,
+                ((z.1 - z.0) / 2) as u32,
+            ),
+            position: ((x.0 + x.1) / 2, (y.0 + y.1) / 2, (z.0 + z.1) / 2),
+            color: color.clone(),
+            ..Default::default()
+        };
+
+        let xs = segments(min.0, max.0, PLATE_XY);
+        let ys = segments(min.1, max.1, PLATE_XY);
+        let zs = segments(min.2, max.2, PLATE_Z);
+        let full_x = (min.0, max.0, true);
+        let full_y = (min.1, max.1, true);
+
+        let mut bricks = vec![];
+        match fill {
+            BoxFill::Solid => bricks.push(region(full_x, full_y, (min.2, max.2, true))),
+            BoxFill::Shell => {
+                for &z in zs.iter() {
+                    if z.2 {
+                        // floors and ceilings span the whole box
+                        bricks.push(region(full_x, full_y, z));
+                        continue;
+                    }
+
+                    for &x in xs.iter() {
+                        if x.2 {
+                            bricks.push(region(x, full_y, z));
+                        } else {
+                            for &y in ys.iter().filter(|y| y.2) {
+                                bricks.push(region(x, y, z));
+                            }
+                        }
+                    }
+                }
+            }
+            BoxFill::Wireframe => {
+                for &z in zs.iter() {
+                    for &x in xs.iter() {
+                        if x.2 && z.2 {
+                            bricks.push(region(x, full_y, z));
+                        } else if x.2 || z.2 {
+                            for &y in ys.iter().filter(|y| y.2) {
+                                bricks.push(region(x, y, z));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        bricks
+    }
+
+    /// Add the bricks of a box to the save. See [`generate_box`](SaveData::generate_box).
+    pub fn add_box(
+        &mut self,
+        min: (i32, i32, i32),
+        max: (i32, i32, i32),
+        fill: BoxFill,
+        brick_asset_index: u32,
+        color: BrickColor,
+    ) {
+        self.bricks
+            .extend(Self::generate_box(min, max, fill, brick_asset_index, color));
+        self.repair_brick_count();
+    }
+
+    /// Create an empty save on the map `map`, authored and hosted by `author`, with `author`
+    /// as its only brick owner.
+    pub fn new(author: User, map: impl Into<String>) -> SaveData {
+        SaveData {
+            header1: Header1 {
+                map: map.into(),
+                author: author.clone(),
+                host: Some(author.clone()),
+                ..Default::default()
+            },
+            header2: Header2 {
+                brick_owners: vec![BrickOwner::from(author)],
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Create an empty save with the headers and preview of `template`.
+    ///
+    /// The new save has no bricks or components, and its brick count and brick owner counts
+    /// are 0.
+    pub fn from_template(template: &SaveData) -> SaveData {
+        let mut data = SaveData {
+            version: template.version,
+            game_version: template.game_version,
+            header1: Header1 {
+                brick_count: 0,
+                ..template.header1.clone()
+            },
+            header2: template.header2.clone(),
+            preview: template.preview.clone(),
+            bricks: vec![],
+            components: HashMap::new(),
+        };
+        data.normalize_owners();
+        data
+    }
+
+    /// Build a save from a voxel grid, placing a 1x1 brick (or, when merging, a larger
+    /// procedural brick) for every occupied cell.
+    ///
+    /// Cell `(x, y, z)` is placed at `(x * 10 + 5, y * 10 + 5, z * 12 + 6)`.
+    pub fn from_voxel_grid(grid: &VoxelGrid, config: VoxelImportConfig) -> SaveData {
+        let white = Color {
+            r: 255,
+            g: 255,
+            b: 255,
+            a: 255,
+        };
+        let color_at = |i: usize| grid.colors.get(i).unwrap_or(&white);
+
+        let mut palette = vec![];
+        let mut palette_indices = HashMap::new();
+        let mut visited = vec![false; grid.width * grid.depth * grid.height];
+        let mut bricks = vec![];
+
+        for z in 0..grid.height {
+            for y in 0..grid.depth {
+                for x in 0..grid.width {
+                    let i = grid.index(x, y, z);
+                    if !grid.is_occupied(x, y, z) || visited[i] {
+                        continue;
+                    }
+
+                    let color = color_at(i);
+                    let free = |x: usize, y: usize, z: usize, visited: &[bool]| {
+                        let i = grid.index(x, y, z);
+                        grid.is_occupied(x, y, z) && !visited[i] && color_at(i) == color
+                    };
+
+                    // greedily grow the brick along X, then Y, then Z
+                    let (mut w, mut d, mut h) = (1, 1, 1);
+                    if config.merge {
+                        while x + w < grid.width && free(x + w, y, z, &visited) {
+                            w += 1;
+                        }
+                        while y + d < grid.depth && (x..x + w).all(|x| free(x, y + d, z, &visited))
+                        {
+                            d += 1;
+                        }
+                        while z + h < grid.height
+                            && (y..y + d).all(|y| (x..x + w).all(|x| free(x, y, z + h, &visited)))
+                        {
+                            h += 1;
+                        }
+                    }
+
+                    for vz in z..z + h {
+                        for vy in y..y + d {
+                            for vx in x..x + w {
+                                visited[grid.index(vx, vy, vz)] = true;
+                            }
+                        }
+                    }
+
+                    let color_index = *palette_indices.entry(color.clone()).or_insert_with(|| {
+                        palette.push(color.clone());
+                        palette.len() as u32 - 1
+                    });
+
+                    bricks.push(Brick {
+                        size: Size::Procedural(5 * w as u32, 5 * d as u32, 6 * h as u32),
+                        position: (
+                            (x * 10 + 5 * w) as i32,
+                            (y * 10 + 5 * d) as i32,
+                            (z * 12 + 6 * h) as i32,
+                        ),
+                        color: BrickColor::Index(color_index),
+                        owner_index: 1,
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+
+        let mut data = SaveData {
+            header1: Header1 {
+                author: config.author.clone(),
+                ..Default::default()
+            },
+            header2: Header2 {
+                brick_assets: vec![config.asset],
+                colors: palette,
+                materials: vec![config.material],
+                brick_owners: vec![config.author.into()],
+                ..Default::default()
+            },
+            bricks,
+            ..Default::default()
+        };
+        data.repair_brick_count();
+        data.normalize_owners();
+        data
+    }
+
+    /// Get the bounds of every brick in the save as `(min_x, min_y, min_z, max_x, max_y, max_z)`,
+    /// accounting for brick size and orientation, or `None` if the save has no bricks.
+    #[cfg(feature = "util")]
+    pub fn bounds(&self) -> Option<(i32, i32, i32, i32, i32, i32)> {
+        self.bricks
+            .iter()
+            .map(|brick| self.brick_bounds(brick))
+            .reduce(|a, b| {
+                (
+                    a.0.min(b.0),
+                    a.1.min(b.1),
+                    a.2.min(b.2),
+                    a.3.max(b.3),
+                    a.4.max(b.4),
+                    a.5.max(b.5),
+                )
+            })
+    }
+
+    /// Get the `(width, depth, height)` of the save's [`bounds`](SaveData::bounds), or `None`
+    /// if the save has no bricks.
+    #[cfg(feature = "util")]
+    pub fn extent(&self) -> Option<(i32, i32, i32)> {
+        self.bounds().map(|b| (b.3 - b.0, b.4 - b.1, b.5 - b.2))
+    }
+
+    /// Get the volume of the save's bounds in cubic units, or 0 if the save has no bricks.
+    #[cfg(feature = "util")]
+    pub fn bounding_volume(&self) -> i64 {
+        self.extent()
+            .map_or(0, |(w, d, h)| w as i64 * d as i64 * h as i64)
+    }
+
+    /// Whether or not the save has bricks and is at most one plate (4 units) tall.
+    #[cfg(feature = "util")]
+    pub fn is_flat(&self) -> bool {
+        matches!(self.extent(), Some((_, _, height)) if height <= 4)
+    }
+
+    /// Get the ratio of the save's width to its depth, or `None` if the save has no bricks or
+    /// no depth.
+    #[cfg(feature = "util")]
+    pub fn aspect_ratio_xy(&self) -> Option<f64> {
+        match self.extent()? {
+            (_, 0, _) => None,
+            (width, depth, _) => Some(width as f64 / depth as f64),
+        }
+    }
+
+    /// Get the bounds of a single brick, in the same form as [`bounds`](SaveData::bounds).
+    #[cfg(feature = "util")]
+    fn brick_bounds(&self, brick: &Brick) -> (i32, i32, i32, i32, i32, i32) {
+        let assets = &self.header2.brick_assets;
+        let size = (
+            crate::util::get_axis_size(brick, assets, 0) as i32,
+            crate::util::get_axis_size(brick, assets, 1) as i32,
+            crate::util::get_axis_size(brick, assets, 2) as i32,
+        );
+        let (x, y, z) = brick.position;
+        (
+            x - size.0,
+            y - size.1,
+            z - size.2,
+            x + size.0,
+            y + size.1,
+            z + size.2,
+        )
+    }
+
+    /// Get the average position of the bricks in the save weighted by the volume of their
+    /// [`size_or_default`](Brick::size_or_default), or `None` if the save has no bricks.
+    pub fn center_of_mass(&self) -> Option<(f64, f64, f64)> {
+        center_of_mass(self.bricks.iter())
+    }
+
+    /// Like [`center_of_mass`](SaveData::center_of_mass), but only for the bricks owned by the
+    /// brick owner with the id `id`.
+    pub fn center_of_mass_by_owner(&self, id: Uuid) -> Option<(f64, f64, f64)> {
+        let owner_index = self.header2.brick_owners.iter().position(|o| o.id == id)? as u32 + 1;
+        center_of_mass(
+            self.bricks
+                .iter()
+                .filter(|brick| brick.owner_index == owner_index),
+        )
+    }
+
+    /// Summarize the bricks of every brick owner, in owner index order, starting with the
+    /// public bricks (owner index 0).
+    ///
+    /// Bricks with an out of range owner index are not counted.
+    #[cfg(feature = "util")]
+    pub fn brick_ownership_report(&self) -> Vec<OwnerReport> {
+        let public = BrickOwner {
+            name: "PUBLIC".into(),
+            id: Uuid::nil(),
+            bricks: 0,
+        };
+        let mut reports = std::iter::once(public)
+            .chain(self.header2.brick_owners.iter().cloned())
+            .map(OwnerReport::new)
+            .collect::<Vec<_>>();
+
+        for brick in self.bricks.iter() {
+            if let Some(report) = reports.get_mut(brick.owner_index as usize) {
+                self.add_to_owner_report(report, brick);
+            }
+        }
+        reports
+    }
+
+    /// Like [`brick_ownership_report`](SaveData::brick_ownership_report), but only for the
+    /// brick owner with the id `id`, or `None` if there is no such owner.
+    #[cfg(feature = "util")]
+    pub fn brick_ownership_report_for(&self, id: Uuid) -> Option<OwnerReport> {
+        let index = self.header2.brick_owners.iter().position(|o| o.id == id)?;
+        let mut report = OwnerReport::new(self.header2.brick_owners[index].clone());
+        for brick in self
+            .bricks
+            .iter()
+            .filter(|brick| brick.owner_index as usize == index + 1)
+        {
+            self.add_to_owner_report(&mut report, brick);
+        }
+        Some(report)
+    }
+
+    #[cfg(feature = "util")]
+    fn add_to_owner_report(&self, report: &mut OwnerReport, brick: &Brick) {
+        report.brick_count += 1;
+        if let Some(asset) = self
+            .header2
+            .brick_assets
+            .get(brick.asset_name_index as usize)
+        {
+            *report.asset_counts.entry(asset.clone()).or_default() += 1;
+        }
+        if let Some(material) = self.header2.materials.get(brick.material_index as usize) {
+            *report.material_counts.entry(material.clone()).or_default() += 1;
+        }
+        report.has_components |= !brick.components.is_empty();
+
+        let b = self.brick_bounds(brick);
+        report.bounds = Some(match report.bounds {
+            Some(a) => (
+                a.0.min(b.0),
+                a.1.min(b.1),
+                a.2.min(b.2),
+                a.3.max(b.3),
+                a.4.max(b.4),
+                a.5.max(b.5),
+            ),
+            None => b,
+        });
+    }
+
+    /// Get the brick with the greatest Z position, or `None` if the save has no bricks.
+    pub fn highest_brick(&self) -> Option<(usize, &Brick)> {
+        self.extremal_brick(2, 1)
+    }
+
+    /// Get the brick with the least Z position, or `None` if the save has no bricks.
+    pub fn lowest_brick(&self) -> Option<(usize, &Brick)> {
+        self.extremal_brick(2, -1)
+    }
+
+    /// Get the brick with the greatest (`direction > 0`) or least (`direction <= 0`) position
+    /// along `axis` (0 = X, 1 = Y, 2 = Z). Ties go to the brick with the lowest index.
+    ///
+    /// Panics if `axis` is greater than 2.
+    pub fn extremal_brick(&self, axis: usize, direction: i32) -> Option<(usize, &Brick)> {
+        let axis = [Axis::X, Axis::Y, Axis::Z][axis];
+        let sign = if direction > 0 { 1 } else { -1 };
+        self.bricks
+            .iter()
+            .enumerate()
+            .fold(None, |best: Option<(usize, &Brick, i64)>, (i, brick)| {
+                let key = sign * axis.coordinate(brick.position) as i64;
+                match best {
+                    Some((_, _, best_key)) if best_key >= key => best,
+                    _ => Some((i, brick, key)),
+                }
+            })
+            .map(|(i, brick, _)| (i, brick))
+    }
+
+    /// Get every brick tied for the least or greatest Z position, in index order.
+    pub fn bricks_at_extremal_z(&self, which: Extremal) -> Vec<(usize, &Brick)> {
+        let z = self.bricks.iter().map(|brick| brick.position.2);
+        let z = match which {
+            Extremal::Min => z.min(),
+            Extremal::Max => z.max(),
+        };
+        match z {
+            Some(z) => self
+                .bricks
+                .iter()
+                .enumerate()
+                .filter(|(_, brick)| brick.position.2 == z)
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Move every brick in the save by `(dx, dy, dz)`.
+    pub fn translate(&mut self, dx: i32, dy: i32, dz: i32) {
+        for brick in self.bricks.iter_mut() {
+            brick.position.0 += dx;
+            brick.position.1 += dy;
+            brick.position.2 += dz;
+        }
+    }
+
+    /// Move every brick in the save to the nearest multiple of `snap_xy` on X and Y, and of
+    /// `snap_z` on Z, rounding halfway positions up. A snap of 0 leaves that axis unchanged.
+    ///
+    /// Snapping can move bricks onto each other; see
+    /// [`find_duplicate_bricks`](SaveData::find_duplicate_bricks).
+    pub fn snap_bricks_to_grid(&mut self, snap_xy: u32, snap_z: u32) {
+        for brick in self.bricks.iter_mut() {
+            let (x, y, z) = brick.position;
+            brick.position = (
+                snap_to_multiple(x, snap_xy),
+                snap_to_multiple(y, snap_xy),
+                snap_to_multiple(z, snap_z),
+            );
+        }
+    }
+
+    /// Scale the size and position of every procedural brick in the save by `scale`, rounding
+    /// to the nearest integer. See [`scale_xyz`](SaveData::scale_xyz).
+    pub fn scale_procedural_bricks(&mut self, scale: f32) -> Result<(), ScaleError> {
+        self.scale_xyz(scale, scale, scale)
+    }
+
+    /// Scale the size and position of every procedural brick in the save by `sx`, `sy`, and
+    /// `sz` along the world X, Y, and Z axes, rounding to the nearest integer.
+    ///
+    /// Bricks with an empty size are left unchanged, as their size comes from their asset.
+    /// Nothing is changed if any brick would end up with a zero or negative size.
+    pub fn scale_xyz(&mut self, sx: f32, sy: f32, sz: f32) -> Result<(), ScaleError> {
+        let scale = [sx, sy, sz];
+        let mut sizes = Vec::with_capacity(self.bricks.len());
+        for (i, brick) in self.bricks.iter().enumerate() {
+            let (x, y, z) = match brick.size {
+                Size::Procedural(x, y, z) => (x, y, z),
+                Size::Empty => {
+                    sizes.push(None);
+                    continue;
+                }
+            };
+
+            let m = brick.orientation_matrix();
+            let mut scaled = [0; 3];
+            for (axis, &size) in [x, y, z].iter().enumerate() {
+                let world_axis = (0..3).find(|&row| m[row][axis] != 0.0).unwrap();
+                scaled[axis] = (size as f32 * scale[world_axis]).round() as u32;
+                if scaled[axis] == 0 && size != 0 {
+                    return Err(ScaleError::NonPositiveSize(i));
+                }
+            }
+            sizes.push(Some(Size::Procedural(scaled[0], scaled[1], scaled[2])));
+        }
+
+        let scale_coordinate = |v: i32, s: f32| (v as f64 * s as f64).round() as i32;
+        for (brick, size) in self.bricks.iter_mut().zip(sizes) {
+            let size = match size {
+                Some(size) => size,
+                None => continue,
+            };
+            let (x, y, z) = brick.position;
+            brick.size = size;
+            brick.position = (
+                scale_coordinate(x, sx),
+                scale_coordinate(y, sy),
+                scale_coordinate(z, sz),
+            );
+        }
+        Ok(())
+    }
+
+    /// Transform every brick in the save by the row-major homogeneous matrix `matrix`.
+    ///
+    /// Brick positions are treated as column vectors and rounded to the nearest integer after
+    /// transforming. The bottom row of the matrix is ignored. Brick directions and rotations
+    /// are updated by the rotation part of the matrix, which must be (a scaled) one of the 24
+    /// axis-aligned rotations. Brick sizes are not scaled.
+    ///
+    /// Nothing is changed if an error is returned.
+    pub fn apply_transform(&mut self, matrix: &[[f32; 4]; 4]) -> Result<(), TransformError> {
+        self.transform_bricks(matrix, false)
+    }
+
+    /// Like [`apply_transform`](SaveData::apply_transform), but fails if a brick would end
+    /// up at a position that is not a whole number instead of rounding it.
+    pub fn apply_transform_strict(&mut self, matrix: &[[f32; 4]; 4]) -> Result<(), TransformError> {
+        self.transform_bricks(matrix, true)
+    }
+
+    fn transform_bricks(
+        &mut self,
+        matrix: &[[f32; 4]; 4],
+        strict: bool,
+    ) -> Result<(), TransformError> {
+        // snap the rotation part to the nearest axis-aligned rotation, ignoring scale
+        let mut rotation = [[0.0f32; 3]; 3];
+        for col in 0..3 {
+            let len = (0..3)
+                .map(|row| matrix[row][col].powi(2))
+                .sum::<f32>()
+                .sqrt();
+            if len == 0.0 {
+                return Err(TransformError::UnsupportedRotation);
+            }
+            for row in 0..3 {
+                rotation[row][col] = (matrix[row][col] / len).round();
+            }
+        }
+        rotation_matrix_to_orientation(&rotation).ok_or(TransformError::UnsupportedRotation)?;
+
+        let mut changes = Vec::with_capacity(self.bricks.len());
+        for (i, brick) in self.bricks.iter().enumerate() {
+            let (x, y, z) = brick.position;
+            let mut position = [0i32; 3];
+            for (row, coord) in position.iter_mut().enumerate() {
+                let m = matrix[row];
+                let v = m[0] as f64 * x as f64
+                    + m[1] as f64 * y as f64
+                    + m[2] as f64 * z as f64
+                    + m[3] as f64;
+                if strict && (v - v.round()).abs() > 1e-3 {
+                    return Err(TransformError::NonIntegerPosition(i));
+                }
+                *coord = v.round() as i32;
+            }
+
+            let m = brick.orientation_matrix();
+            let mut oriented = [[0.0f32; 3]; 3];
+            for (row, oriented_row) in oriented.iter_mut().enumerate() {
+                for (col, entry) in oriented_row.iter_mut().enumerate() {
+                    *entry = (0..3).map(|k| rotation[row][k] * m[k][col]).sum();
+                }
+            }
+            let orientation = rotation_matrix_to_orientation(&oriented)
+                .ok_or(TransformError::UnsupportedRotation)?;
+            changes.push(((position[0], position[1], position[2]), orientation));
+        }
+
+        for (brick, (position, (direction, rotation))) in self.bricks.iter_mut().zip(changes) {
+            brick.position = position;
+            brick.direction = direction;
+            brick.rotation = rotation;
+        }
+        Ok(())
+    }
+
+    /// Move every brick in the save so that the center of its bounds is at the origin.
+    #[cfg(feature = "util")]
+    pub fn center_bricks(&mut self) {
+        self.center_bricks_at((0, 0, 0));
+    }
+
+    /// Move every brick in the save so that the center of its bounds is at `target`.
+    ///
+    /// Centers that fall between two units are rounded to the nearest integer.
+    #[cfg(feature = "util")]
+    pub fn center_bricks_at(&mut self, target: (i32, i32, i32)) {
+        if let Some(bounds) = self.bounds() {
+            let center = bounds_center(bounds);
+            self.translate(
+                target.0 - center.0,
+                target.1 - center.1,
+                target.2 - center.2,
+            );
+        }
+    }
+
+    /// Move every brick in the save so that the center of its bounds is at the origin on X and Y,
+    /// and the bottom of its bounds is at Z = 0.
+    #[cfg(feature = "util")]
+    pub fn center_bricks_z_floor(&mut self) {
+        if let Some(bounds) = self.bounds() {
+            let center = bounds_center(bounds);
+            self.translate(-center.0, -center.1, -bounds.2);
+        }
+    }
+
+    /// Add every brick and component from `other` to this save.
+    ///
+    /// Brick assets, materials, physical materials, mods, colors, and owners from `other` are
+    /// reused when this save already has a matching entry and appended otherwise, and the
+    /// bricks from `other` are remapped to match. Components with the same name are combined,
+    /// keeping this save's version.
+    ///
+    /// Components with the same name must have the same properties, or this save is left
+    /// unchanged and an error is returned.
+    pub fn merge(&mut self, other: SaveData) -> Result<(), MergeError> {
+        for (name, component) in other.components.iter() {
+            if let Some(existing) = self.components.get(name) {
+                if existing.properties != component.properties {
+                    return Err(MergeError::ComponentPropertyMismatch(name.clone()));
+                }
+            }
+        }
+
+        let assets = merge_entries(&mut self.header2.brick_assets, other.header2.brick_assets);
+        let materials = merge_entries(&mut self.header2.materials, other.header2.materials);
+        let physical = merge_entries(
+            &mut self.header2.physical_materials,
+            other.header2.physical_materials,
+        );
+        let colors = merge_entries(&mut self.header2.colors, other.header2.colors);
+        merge_entries(&mut self.header2.mods, other.header2.mods);
+
+        let mut owners = Vec::with_capacity(other.header2.brick_owners.len());
+        for owner in other.header2.brick_owners {
+            let existing = self
+                .header2
+                .brick_owners
+                .iter()
+                .position(|o| o.id == owner.id);
+            owners.push(match existing {
+                Some(i) => i as u32,
+                None => {
+                    self.header2.brick_owners.push(owner);
+                    self.header2.brick_owners.len() as u32 - 1
+                }
+            });
+        }
+
+        let offset = self.bricks.len() as u32;
+        for mut brick in other.bricks {
+            brick.asset_name_index = remap_entry(&assets, brick.asset_name_index);
+            brick.material_index = remap_entry(&materials, brick.material_index);
+            brick.physical_index = remap_entry(&physical, brick.physical_index);
+            if let BrickColor::Index(index) = brick.color {
+                brick.color = BrickColor::Index(remap_entry(&colors, index));
+            }
+            if brick.owner_index > 0 {
+                brick.owner_index = remap_entry(&owners, brick.owner_index - 1) + 1;
+            }
+            self.bricks.push(brick);
+        }
+
+        for (name, component) in other.components {
+            let brick_indices = component.brick_indices.iter().map(|&i| i + offset);
+            match self.components.get_mut(&name) {
+                Some(existing) => existing.brick_indices.extend(brick_indices),
+                None => {
+                    let brick_indices = brick_indices.collect();
+                    self.components.insert(
+                        name,
+                        Component {
+                            brick_indices,
+                            ..component
+                        },
+                    );
+                }
+            }
+        }
+
+        self.repair_brick_count();
+        self.normalize_owners();
+        Ok(())
+    }
+
+    /// Merge `other` into this save, placed `gap` units above the top of this save's bounds.
+    ///
+    /// A `gap` of 0 leaves the two saves touching. If this save has no bricks, `other` is
+    /// merged in without being moved. See [`merge`](SaveData::merge) for when this fails.
+    #[cfg(feature = "util")]
+    pub fn stack(&mut self, other: SaveData, gap: i32) -> Result<(), MergeError> {
+        self.arrange_side_by_side(other, Axis::Z, gap)
+    }
+
+    /// Merge `other` into this save, placed `gap` units past the end of this save's bounds
+    /// along `axis`. Positions along the other axes are left unchanged.
+    ///
+    /// If this save has no bricks, `other` is merged in without being moved. See
+    /// [`merge`](SaveData::merge) for when this fails.
+    #[cfg(feature = "util")]
+    pub fn arrange_side_by_side(
+        &mut self,
+        mut other: SaveData,
+        axis: Axis,
+        gap: i32,
+    ) -> Result<(), MergeError> {
+        if let (Some(bounds), Some(other_bounds)) = (self.bounds(), other.bounds()) {
+            let max = axis.coordinate((bounds.3, bounds.4, bounds.5));
+            let other_min = axis.coordinate((other_bounds.0, other_bounds.1, other_bounds.2));
+            let offset = max + gap - other_min;
+            match axis {
+                Axis::X => other.translate(offset, 0, 0),
+                Axis::Y => other.translate(0, offset, 0),
+                Axis::Z => other.translate(0, 0, offset),
+            }
+        }
+        self.merge(other)
+    }
+
+    /// Rename the brick asset `old` to `new`. Returns whether or not `old` was found.
+    ///
+    /// Bricks refer to assets by index, so they are unaffected.
+    pub fn replace_asset(&mut self, old: &str, new: &str) -> bool {
+        rename_entry(&mut self.header2.brick_assets, old, new)
+    }
+
+    /// Rename the material `old` to `new`. Returns whether or not `old` was found.
+    ///
+    /// Bricks refer to materials by index, so they are unaffected.
+    pub fn rename_material(&mut self, old: &str, new: &str) -> bool {
+        rename_entry(&mut self.header2.materials, old, new)
+    }
+
+    /// Rename the mod `old` to `new`. Returns whether or not `old` was found.
+    pub fn replace_mod(&mut self, old: &str, new: &str) -> bool {
+        rename_entry(&mut self.header2.mods, old, new)
+    }
+
+    /// Get the mods in `header2` that are actually used by a brick in the save.
+    ///
+    /// Saves do not record which mod an asset comes from, so a mod is considered used when a
+    /// brick asset, material, or physical material referenced by at least one brick is prefixed
+    /// by its name. Mod assets are conventionally named `Mod_<mod>_<asset>`, e.g. `Mod_Foo_PB_Bar`
+    /// for the mod `Foo` (or `Mod_Foo`), so an asset only counts for a mod when the mod name is
+    /// followed by `_`.
+    pub fn required_mods(&self) -> HashSet<String> {
+        let header2 = &self.header2;
+        let mut used = HashSet::new();
+        for brick in self.bricks.iter() {
+            used.extend(header2.brick_assets.get(brick.asset_name_index as usize));
+            used.extend(header2.materials.get(brick.material_index as usize));
+            used.extend(
+                header2
+                    .physical_materials
+                    .get(brick.physical_index as usize),
+            );
+        }
+
+        header2
+            .mods
+            .iter()
+            .filter(|m| {
+                let prefix = if m.starts_with("Mod_") {
+                    format!("{}_", m)
+                } else {
+                    format!("Mod_{}_", m)
+                };
+                !m.is_empty() && used.iter().any(|name| name.starts_with(&prefix))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Remove mods from `header2` that are not [required](SaveData::required_mods).
+    /// Returns the number of mods removed.
+    pub fn strip_unused_mods(&mut self) -> usize {
+        let required = self.required_mods();
+        let before = self.header2.mods.len();
+        self.header2.mods.retain(|m| required.contains(m));
+        before - self.header2.mods.len()
+    }
+
+    /// Get every distinct string in the save's headers, brick owner names, and brick
+    /// component `String` properties, sorted.
+    pub fn collect_all_strings(&self) -> Vec<&str> {
+        let (header1, header2) = (&self.header1, &self.header2);
+        let mut strings = vec![
+            header1.map.as_str(),
+            &header1.description,
+            &header1.author.name,
+        ];
+        strings.extend(header1.host.iter().map(|host| host.name.as_str()));
+        strings.extend(
+            header2
+                .mods
+                .iter()
+                .chain(&header2.brick_assets)
+                .chain(&header2.materials)
+                .chain(&header2.physical_materials)
+                .chain(&header2.texture_assets)
+                .map(String::as_str),
+        );
+        strings.extend(header2.brick_owners.iter().map(|owner| owner.name.as_str()));
+        strings.extend(
+            self.bricks
+                .iter()
+                .flat_map(|brick| brick.components.values())
+                .flat_map(|props| props.values())
+                .filter_map(|value| match value {
+                    UnrealType::String(string) => Some(string.as_str()),
+                    _ => None,
+                }),
+        );
+
+        strings.sort_unstable();
+        strings.dedup();
+        strings
+    }
+
+    /// Build a map from position to the indices of every brick at that exact position.
+    ///
+    /// The map is not kept up to date with changes to the bricks.
+    pub fn build_position_index(&self) -> HashMap<(i32, i32, i32), Vec<usize>> {
+        let mut index: HashMap<_, Vec<_>> = HashMap::new();
+        for (i, brick) in self.bricks.iter().enumerate() {
+            index.entry(brick.position).or_default().push(i);
+        }
+        index
+    }
+
+    /// Check if there is a brick at exactly `pos`.
+    ///
+    /// This scans every brick; for many lookups, use
+    /// [`build_position_index`](SaveData::build_position_index) instead.
+    pub fn has_brick_at(&self, pos: (i32, i32, i32)) -> bool {
+        self.bricks.iter().any(|brick| brick.position == pos)
+    }
+
+    /// Find every pair of bricks that share a position, asset, direction, and rotation.
+    ///
+    /// Each pair is reported once as `(first, second)` with `first < second`.
+    pub fn find_duplicate_bricks(&self) -> Vec<(usize, usize)> {
+        let mut groups: HashMap<_, Vec<usize>> = HashMap::new();
+        for (i, brick) in self.bricks.iter().enumerate() {
+            groups
+                .entry((
+                    brick.position,
+                    brick.asset_name_index,
+                    brick.direction,
+                    brick.rotation,
+                ))
+                .or_default()
+                .push(i);
+        }
+
+        let mut pairs = vec![];
+        for group in groups.values() {
+            for (n, &a) in group.iter().enumerate() {
+                pairs.extend(group[n + 1..].iter().map(|&b| (a, b)));
+            }
+        }
+        pairs.sort_unstable();
+        pairs
+    }
+
+    /// Find every pair of bricks whose bounding boxes overlap, regardless of asset.
+    /// Bricks that only touch are not considered overlapping.
+    ///
+    /// Each pair is reported once as `(first, second)` with `first < second`.
+    #[cfg(feature = "util")]
+    pub fn find_overlapping_bricks(&self) -> Vec<(usize, usize)> {
+        let mut bounds = self
+            .bricks
+            .iter()
+            .map(|brick| self.brick_bounds(brick))
+            .enumerate()
+            .filter(|(_, b)| b.0 < b.3 && b.1 < b.4 && b.2 < b.5)
+            .collect::<Vec<_>>();
+        bounds.sort_unstable_by_key(|(_, b)| b.0);
+
+        // sweep along X, only comparing bricks whose X ranges overlap
+        let mut pairs = vec![];
+        for (n, &(i, a)) in bounds.iter().enumerate() {
+            for &(j, b) in bounds[n + 1..].iter().take_while(|(_, b)| b.0 < a.3) {
+                if a.1 < b.4 && b.1 < a.4 && a.2 < b.5 && b.2 < a.5 {
+                    pairs.push((i.min(j), i.max(j)));
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs
+    }
+
+    /// Roughly estimate the size in bytes of the save once written with a compressing
+    /// [`SaveWriter`](crate::write::SaveWriter).
+    ///
+    /// Section sizes are computed from the save's contents (bricks are sampled from the
+    /// start of the brick list), then scaled by a typical zlib compression ratio, so the
+    /// result is only a ballpark figure.
+    pub fn estimate_file_size(&self) -> usize {
+        // bit packed brick data compresses poorly, so zlib usually only shrinks it a little
+        const COMPRESSION_RATIO: f64 = 0.9;
+        const SAMPLE_BRICKS: usize = 1000;
+        // the uncompressed and compressed size of each section
+        const SECTION_HEADER: usize = 8;
+
+        let compressed = |size: usize| SECTION_HEADER + (size as f64 * COMPRESSION_RATIO) as usize;
+
+        let header0 = MAGIC_BYTES.len() + 2 + 4;
+
+        let host = self.header1.host.as_ref().unwrap_or(&self.header1.author);
+        let header1 = estimated_string_size(&self.header1.map)
+            + estimated_string_size(&self.header1.author.name)
+            + estimated_string_size(&self.header1.description)
+            + estimated_string_size(&host.name)
+            + 16 * 2
+            + 8
+            + 4;
+
+        let header2 = 4 * 6
+            + estimated_strings_size(&self.header2.mods)
+            + estimated_strings_size(&self.header2.brick_assets)
+            + 4 * self.header2.colors.len()
+            + estimated_strings_size(&self.header2.materials)
+            + self
+                .header2
+                .brick_owners
+                .iter()
+                .map(|owner| 16 + estimated_string_size(&owner.name) + 4)
+                .sum::<usize>()
+            + estimated_strings_size(&self.header2.physical_materials);
+
+        let preview = 1 + match &self.preview {
+            Preview::None => 0,
+            Preview::PNG(bytes) | Preview::JPEG(bytes) | Preview::Unknown(_, bytes) => {
+                4 + bytes.len()
+            }
+        };
+
+        let asset_bits = uint_bits(self.header2.brick_assets.len());
+        let material_bits = uint_bits(self.header2.materials.len());
+        let physical_bits = uint_bits(self.header2.physical_materials.len());
+        let color_bits = uint_bits(self.header2.colors.len());
+        let sample = &self.bricks[..self.bricks.len().min(SAMPLE_BRICKS)];
+        let sample_bits = sample
+            .iter()
+            .map(|brick| {
+                let size_bits = match brick.size {
+                    Size::Procedural(x, y, z) => {
+                        1 + packed_bits(x) + packed_bits(y) + packed_bits(z)
+                    }
+                    Size::Empty => 1,
+                };
+                let (x, y, z) = brick.position;
+                let color_bits = match brick.color {
+                    BrickColor::Index(_) => 1 + color_bits,
+                    BrickColor::Unique(_) => 1 + 24,
+                };
+                let bits = asset_bits
+                    + size_bits
+                    + packed_bits((x.unsigned_abs() << 1) | 1)
+                    + packed_bits((y.unsigned_abs() << 1) | 1)
+                    + packed_bits((z.unsigned_abs() << 1) | 1)
+                    + 5 // orientation
+                    + 5 // collision and visibility
+                    + material_bits
+                    + physical_bits
+                    + 11 // material intensity
+                    + color_bits
+                    + packed_bits(brick.owner_index);
+                // every brick is byte aligned
+                bits.div_ceil(8) * 8
+            })
+            .sum::<usize>();
+        let bricks = match sample.len() {
+            0 => 0,
+            n => sample_bits * self.bricks.len() / n / 8,
+        };
+
+        let mut components = 4;
+        for (name, component) in self.components.iter() {
+            components += estimated_string_size(name)
+                + 4 // section length
+                + 4 // version
+                + 4
+                + (component.brick_indices.len() * uint_bits(self.bricks.len())).div_ceil(8)
+                + 4
+                + component
+                    .properties
+                    .iter()
+                    .map(|(key, ty)| estimated_string_size(key) + estimated_string_size(ty))
+                    .sum::<usize>();
+        }
+        components += self
+            .bricks
+            .iter()
+            .flat_map(|brick| brick.components.values())
+            .flat_map(|properties| properties.values())
+            .map(|value| match value {
+                UnrealType::Boolean(_) | UnrealType::Float(_) | UnrealType::Color(_) => 4,
+                UnrealType::Byte(_) => 1,
+                UnrealType::Class(s) | UnrealType::String(s) => estimated_string_size(s),
+                UnrealType::Rotator(..) => 12,
+                UnrealType::LinearColor(..) => 16,
+            })
+            .sum::<usize>();
+
+        header0
+            + compressed(header1)
+            + compressed(header2)
+            + preview
+            + compressed(bricks)
+            + compressed(components)
+    }
+
+    /// Export the brick assets, materials, colors, physical materials, and owners of the save
+    /// as a JSON object. Each list is in index order.
+    #[cfg(feature = "schema")]
+    pub fn to_schema_json(&self) -> serde_json::Value {
+        let header2 = &self.header2;
+        serde_json::json!({
+            "brick_assets": header2.brick_assets,
+            "materials": header2.materials,
+            "colors": header2
+                .colors
+                .iter()
+                .map(|c| [c.r, c.g, c.b, c.a])
+                .collect::<Vec<_>>(),
+            "physical_materials": header2.physical_materials,
+            "owners": header2
+                .brick_owners
+                .iter()
+                .map(|owner| {
+                    serde_json::json!({
+                        "name": owner.name,
+                        "id": owner.id.to_string(),
+                        "bricks": owner.bricks,
+                    })
+                })
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    /// Export the schema of the save as a JSON string. See [`to_schema_json`](SaveData::to_schema_json).
+    #[cfg(feature = "schema")]
+    pub fn to_schema_string(&self) -> String {
+        self.to_schema_json().to_string()
+    }
+
+    /// Write the save to `writer` as [JSON Lines](https://jsonlines.org): a line with
+    /// everything but the bricks, then a line for each brick.
+    ///
+    /// Brick lines also include the `asset`, `material`, and `physical_material` names their
+    /// indices refer to, when in range. The preview is not written.
+    #[cfg(feature = "serialize")]
+    pub fn export_as_json_lines(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        let header = JsonLinesHeader {
+            version: self.version,
+            game_version: self.game_version,
+            header1: Cow::Borrowed(&self.header1),
+            header2: Cow::Borrowed(&self.header2),
+            components: Cow::Borrowed(&self.components),
+        };
+        serde_json::to_writer(&mut *writer, &header)?;
+        writer.write_all(b"\n")?;
+
+        fn name(list: &[String], index: u32) -> Option<Cow<'_, str>> {
+            list.get(index as usize).map(Cow::from)
+        }
+
+        let header2 = &self.header2;
+        for brick in self.bricks.iter() {
+            let line = JsonLinesBrick {
+                brick: Cow::Borrowed(brick),
+                asset: name(&header2.brick_assets, brick.asset_name_index),
+                material: name(&header2.materials, brick.material_index),
+                physical_material: name(&header2.physical_materials, brick.physical_index),
+            };
+            serde_json::to_writer(&mut *writer, &line)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Read a save written with [`export_as_json_lines`](SaveData::export_as_json_lines).
+    ///
+    /// A brick's asset, material, and physical material names take precedence over its
+    /// indices, and are added to `header2` if they are not there yet. Blank lines are ignored.
+    #[cfg(feature = "serialize")]
+    pub fn import_from_json_lines(reader: impl Read) -> Result<SaveData, JsonLinesError> {
+        let mut lines = io::BufRead::lines(io::BufReader::new(reader))
+            .enumerate()
+            .map(|(i, line)| line.map(|line| (i + 1, line)))
+            .filter(|line| !matches!(line, Ok((_, line)) if line.trim().is_empty()));
+        let parse_error = |line| move |source| JsonLinesError::InvalidJson { line, source };
+
+        let (line, header) = lines.next().ok_or(JsonLinesError::MissingHeader)??;
+        let header: JsonLinesHeader = serde_json::from_str(&header).map_err(parse_error(line))?;
+        let mut data = SaveData {
+            version: header.version,
+            game_version: header.game_version,
+            header1: header.header1.into_owned(),
+            header2: header.header2.into_owned(),
+            preview: Preview::None,
+            bricks: vec![],
+            components: header.components.into_owned(),
+        };
+
+        for line in lines {
+            let (line, brick) = line?;
+            let brick: JsonLinesBrick = serde_json::from_str(&brick).map_err(parse_error(line))?;
+            let mut resolved = brick.brick.into_owned();
+            let header2 = &mut data.header2;
+            resolved.asset_name_index = resolve_name(
+                &mut header2.brick_assets,
+                resolved.asset_name_index,
+                brick.asset,
+            );
+            resolved.material_index = resolve_name(
+                &mut header2.materials,
+                resolved.material_index,
+                brick.material,
+            );
+            resolved.physical_index = resolve_name(
+                &mut header2.physical_materials,
+                resolved.physical_index,
+                brick.physical_material,
+            );
+            data.bricks.push(resolved);
+        }
+
+        data.repair_brick_count();
+        Ok(data)
+    }
+
+    /// Borrow the save as an [`InternedSave`], which resolves brick asset and material
+    /// indices to names and names back to indices.
+    pub fn with_interning(&self) -> InternedSave<'_> {
+        fn lookup(list: &[String]) -> HashMap<&str, u32> {
+            let mut map = HashMap::with_capacity(list.len());
+            for (i, name) in list.iter().enumerate() {
+                map.entry(name.as_str()).or_insert(i as u32);
+            }
+            map
+        }
+
+        InternedSave {
+            data: self,
+            assets: lookup(&self.header2.brick_assets),
+            materials: lookup(&self.header2.materials),
+            physical_materials: lookup(&self.header2.physical_materials),
+        }
+    }
+
+    /// Stable sort the bricks so that similar bricks are adjacent, which improves the
+    /// compression ratio of the brick section.
+    ///
+    /// Bricks are sorted by asset, material, direction, rotation, and then Z position.
+    /// Component brick indices are remapped to follow their bricks.
+    pub fn sort_bricks_for_compression(&mut self) {
+        let mut order = (0..self.bricks.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| {
+            let brick = &self.bricks[i];
+            (
+                brick.asset_name_index,
+                brick.material_index,
+                brick.direction as u8,
+                brick.rotation as u8,
+                brick.position.2,
+            )
+        });
+        self.permute_bricks(&order);
+    }
+
+    /// Stable sort the bricks by position, ordered by Z, then Y, then X.
+    /// Component brick indices are remapped to follow their bricks.
+    pub fn sort_bricks_by_position(&mut self) {
+        self.sort_bricks_by(|a, b| {
+            (a.position.2, a.position.1, a.position.0).cmp(&(
+                b.position.2,
+                b.position.1,
+                b.position.0,
+            ))
+        });
+    }
+
+    /// Stable sort the bricks with the comparator `cmp`.
+    /// Component brick indices are remapped to follow their bricks.
+    pub fn sort_bricks_by<F: Fn(&Brick, &Brick) -> std::cmp::Ordering>(&mut self, cmp: F) {
+        let mut order = (0..self.bricks.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| cmp(&self.bricks[a], &self.bricks[b]));
+        self.permute_bricks(&order);
+    }
+
+    /// Reorder the bricks so that the brick at `order[i]` moves to index `i`, remapping
+    /// component brick indices to match.
+    fn permute_bricks(&mut self, order: &[usize]) {
+        let mut old_to_new = vec![0u32; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            old_to_new[old] = new as u32;
+        }
+
+        let mut old_bricks = std::mem::take(&mut self.bricks)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        self.bricks = order
+            .iter()
+            .map(|&old| old_bricks[old].take().unwrap())
+            .collect();
+
+        for component in self.components.values_mut() {
+            for index in component.brick_indices.iter_mut() {
+                if let Some(&new) = old_to_new.get(*index as usize) {
+                    *index = new;
+                }
+            }
+            component.brick_indices.sort_unstable();
+        }
+    }
+}
+
+/// The first line of [`SaveData::export_as_json_lines`].
+#[cfg(feature = "serialize")]
+#[derive(Serialize, Deserialize)]
+struct JsonLinesHeader<'a> {
+    version: u16,
+    game_version: i32,
+    #[serde(flatten)]
+    header1: Cow<'a, Header1>,
+    #[serde(flatten)]
+    header2: Cow<'a, Header2>,
+    components: Cow<'a, HashMap<String, Component>>,
+}
+
+/// A brick line of [`SaveData::export_as_json_lines`].
+#[cfg(feature = "serialize")]
+#[derive(Serialize, Deserialize)]
+struct JsonLinesBrick<'a> {
+    #[serde(flatten)]
+    brick: Cow<'a, Brick>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    asset: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    material: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    physical_material: Option<Cow<'a, str>>,
+}
+
+/// Get the index of `name` in `list`, preferring `index` if it matches and adding `name` to
+/// the end if it is missing. Without a name, `index` is returned as-is.
+#[cfg(feature = "serialize")]
+fn resolve_name(list: &mut Vec<String>, index: u32, name: Option<Cow<str>>) -> u32 {
+    let name = match name {
+        Some(name) => name,
+        None => return index,
+    };
+    if list.get(index as usize).is_some_and(|entry| *entry == name) {
+        return index;
+    }
+    match list.iter().position(|entry| *entry == name) {
+        Some(i) => i as u32,
+        None => {
+            list.push(name.into_owned());
+            list.len() as u32 - 1
+        }
+    }
+}
+
+/// Round `value` to the nearest multiple of `snap`, rounding halfway values up.
+fn snap_to_multiple(value: i32, snap: u32) -> i32 {
+    if snap == 0 {
+        return value;
+    }
+    let (value, snap) = (value as i64, snap as i64);
+    let rest = value.rem_euclid(snap);
+    let snapped = if rest * 2 >= snap {
+        value - rest + snap
+    } else {
+        value - rest
+    };
+    snapped.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+/// Get the center of bounds returned by [`SaveData::bounds`], rounded to the nearest integer.
+#[cfg(feature = "util")]
+fn bounds_center(bounds: (i32, i32, i32, i32, i32, i32)) -> (i32, i32, i32) {
+    let mid = |min: i32, max: i32| ((min as f64 + max as f64) / 2.0).round() as i32;
+    (
+        mid(bounds.0, bounds.3),
+        mid(bounds.1, bounds.4),
+        mid(bounds.2, bounds.5),
+    )
+}
+
+/// Get the Z of the top of the nearest brick below `brick` and above `z_floor`, or `z_floor`
+/// if there is none. Returns `None` if `brick` is at or below `z_floor`, or if another brick
+/// is directly under it.
+#[cfg(feature = "util")]
+fn unsupported_floor(
+    octree: &crate::util::octree::SaveOctree,
+    brick: &Brick,
+    z_floor: i32,
+) -> Option<i32> {
+    let (min, max) = octree.brick_bounds(brick);
+    if min.2 <= z_floor || !octree.brick_side(brick, Direction::ZNegative).is_empty() {
+        return None;
+    }
+
+    let floor = octree
+        .bricks_in((min.0, min.1, z_floor), (max.0, max.1, min.2))
+        .into_iter()
+        .map(|below| octree.brick_bounds(below).1 .2)
+        .filter(|&top| top <= min.2)
+        .fold(z_floor, std::cmp::max);
+    Some(floor)
+}
+
+/// Get the index and bottom face center of every brick above Z = 0 with nothing directly
+/// under it.
+#[cfg(feature = "util")]
+fn overhangs(octree: &crate::util::octree::SaveOctree) -> Vec<(usize, (i32, i32, i32))> {
+    octree
+        .data()
+        .bricks
+        .iter()
+        .enumerate()
+        .filter(|(_, brick)| unsupported_floor(octree, brick, 0).is_some())
+        .map(|(i, brick)| {
+            let bottom = octree.brick_bounds(brick).0 .2;
+            (i, (brick.position.0, brick.position.1, bottom))
+        })
+        .collect()
+}
+
+/// Get the average position of `bricks` weighted by their volume, or `None` if there are none.
+fn center_of_mass<'a>(bricks: impl Iterator<Item = &'a Brick>) -> Option<(f64, f64, f64)> {
+    let (mut x, mut y, mut z, mut total) = (0.0, 0.0, 0.0, 0.0);
+    for brick in bricks {
+        let (sx, sy, sz) = brick.size_or_default();
+        let weight = sx as f64 * sy as f64 * sz as f64;
+        x += brick.position.0 as f64 * weight;
+        y += brick.position.1 as f64 * weight;
+        z += brick.position.2 as f64 * weight;
+        total += weight;
+    }
+
+    if total == 0.0 {
+        None
+    } else {
+        Some((x / total, y / total, z / total))
+    }
+}
+
+/// Append every entry of `other` that is not already in `list`, returning the index in
+/// `list` of each entry of `other`.
+fn merge_entries<T: PartialEq>(list: &mut Vec<T>, other: Vec<T>) -> Vec<u32> {
+    other
+        .into_iter()
+        .map(|entry| match list.iter().position(|e| *e == entry) {
+            Some(i) => i as u32,
+            None => {
+                list.push(entry);
+                list.len() as u32 - 1
+            }
+        })
+        .collect()
+}
+
+/// Replace the first entry equal to `old` in `list` with `new`, returning whether or not it was found.
+fn rename_entry(list: &mut [String], old: &str, new: &str) -> bool {
+    match list.iter_mut().find(|entry| *entry == old) {
+        Some(entry) => {
+            *entry = new.to_owned();
+            true
+        }
+        None => false,
+    }
+}
+
+/// A read-only view of a save that looks up brick asset and material names without
+/// allocating. Created with [`SaveData::with_interning`].
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize), serde(default))]
-pub struct SaveData {
-    /// The version of the save. Only relevant for reads; this automatically uses [`SAVE_VERSION`](crate::SAVE_VERSION) when writing.
-    pub version: u16,
+pub struct InternedSave<'a> {
+    data: &'a SaveData,
+    assets: HashMap<&'a str, u32>,
+    materials: HashMap<&'a str, u32>,
+    physical_materials: HashMap<&'a str, u32>,
+}
 
-    /// The game version the save was saved on.
-    pub game_version: i32,
+impl<'a> InternedSave<'a> {
+    /// The underlying save.
+    #[inline]
+    pub fn data(&self) -> &'a SaveData {
+        self.data
+    }
 
-    /// The first header of the save.
-    #[cfg_attr(feature = "serialize", serde(flatten))]
-    pub header1: Header1,
+    /// The name of a brick's asset.
+    #[inline]
+    pub fn asset_name(&self, brick: &Brick) -> Option<&'a str> {
+        brick.asset_name(&self.data.header2)
+    }
 
-    /// The second header of the save.
-    #[cfg_attr(feature = "serialize", serde(flatten))]
-    pub header2: Header2,
+    /// The name of a brick's material.
+    #[inline]
+    pub fn material_name(&self, brick: &Brick) -> Option<&'a str> {
+        self.data
+            .header2
+            .materials
+            .get(brick.material_index as usize)
+            .map(String::as_str)
+    }
 
-    /// The preview of the save, if any.
-    #[cfg_attr(feature = "serialize", serde(skip))]
-    pub preview: Preview,
+    /// The name of a brick's physical material.
+    #[inline]
+    pub fn physical_material_name(&self, brick: &Brick) -> Option<&'a str> {
+        self.data
+            .header2
+            .physical_materials
+            .get(brick.physical_index as usize)
+            .map(String::as_str)
+    }
 
-    /// The bricks in the save.
-    pub bricks: Vec<Brick>,
+    /// The index of the brick asset `name`, if it is in the save.
+    #[inline]
+    pub fn asset_index(&self, name: &str) -> Option<u32> {
+        self.assets.get(name).copied()
+    }
 
-    /// The components in the save.
-    pub components: HashMap<String, Component>,
+    /// The index of the material `name`, if it is in the save.
+    #[inline]
+    pub fn material_index(&self, name: &str) -> Option<u32> {
+        self.materials.get(name).copied()
+    }
+
+    /// The index of the physical material `name`, if it is in the save.
+    #[inline]
+    pub fn physical_material_index(&self, name: &str) -> Option<u32> {
+        self.physical_materials.get(name).copied()
+    }
 }
 
-impl SaveData {
-    /// Convert this `SaveData` into a `SaveOctree` for quick traversal of bricks in space.
-    #[cfg(feature = "util")]
-    pub fn into_octree(self) -> crate::util::octree::SaveOctree {
-        crate::util::octree::SaveOctree::new(self)
+/// The number of bytes a string takes up when written.
+fn estimated_string_size(string: &str) -> usize {
+    4 + match string {
+        "" => 0,
+        _ if string.is_ascii() => string.len() + 1,
+        _ => string.encode_utf16().count() * 2 + 1,
+    }
+}
+
+/// The number of bytes an array of strings takes up when written, excluding its length.
+fn estimated_strings_size(strings: &[String]) -> usize {
+    strings.iter().map(|s| estimated_string_size(s)).sum()
+}
+
+/// The number of bits used to write an index into a list of `len` entries.
+fn uint_bits(len: usize) -> usize {
+    let max = len.max(2) as u32;
+    (32 - (max - 1).leading_zeros()) as usize
+}
+
+/// The number of bits used to write a packed uint.
+fn packed_bits(value: u32) -> usize {
+    let significant = (32 - value.leading_zeros() as usize).max(1);
+    significant.div_ceil(7) * 8
+}
+
+fn mark_used(used: &mut [bool], index: u32) {
+    if let Some(entry) = used.get_mut(index as usize) {
+        *entry = true;
+    }
+}
+
+/// Remove every entry of `list` that is not marked in `used`, returning the new index of
+/// each old index.
+fn compact_entries<T>(list: &mut Vec<T>, used: &[bool]) -> Vec<u32> {
+    let mut remap = Vec::with_capacity(used.len());
+    let mut next = 0;
+    for &kept in used.iter() {
+        remap.push(next);
+        if kept {
+            next += 1;
+        }
+    }
+
+    let mut i = 0;
+    list.retain(|_| {
+        i += 1;
+        used[i - 1]
+    });
+    remap
+}
+
+/// Look up the new index of `index` after [`compact_entries`]. Out of range indices are
+/// left alone.
+fn remap_entry(remap: &[u32], index: u32) -> u32 {
+    remap.get(index as usize).copied().unwrap_or(index)
+}
+
+/// How a box generated by [`SaveData::generate_box`] is filled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BoxFill {
+    /// The entire volume of the box.
+    Solid,
+    /// The faces of the box, one plate thick.
+    Shell,
+    /// The edges of the box, one plate thick.
+    Wireframe,
+}
+
+/// A 3D grid of voxels, used by [`SaveData::from_voxel_grid`].
+///
+/// Cells are stored X first, then Y, then Z, so cell `(x, y, z)` is at index
+/// `x + y * width + z * width * depth`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VoxelGrid {
+    /// The size of the grid along X.
+    pub width: usize,
+    /// The size of the grid along Y.
+    pub depth: usize,
+    /// The size of the grid along Z.
+    pub height: usize,
+    /// Whether or not each cell is filled.
+    pub occupied: Vec<bool>,
+    /// The color of each cell. Cells without a color are white.
+    pub colors: Vec<Color>,
+}
+
+impl VoxelGrid {
+    /// The index of cell `(x, y, z)` in `occupied` and `colors`.
+    #[inline]
+    pub fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        x + y * self.width + z * self.width * self.depth
+    }
+
+    /// Whether or not cell `(x, y, z)` is filled.
+    #[inline]
+    pub fn is_occupied(&self, x: usize, y: usize, z: usize) -> bool {
+        self.occupied
+            .get(self.index(x, y, z))
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+/// Options for [`SaveData::from_voxel_grid`].
+#[derive(Debug, Clone)]
+pub struct VoxelImportConfig {
+    /// The brick asset every brick uses.
+    pub asset: String,
+    /// The material every brick uses.
+    pub material: String,
+    /// Whether or not to merge neighbouring voxels of the same color into larger bricks.
+    pub merge: bool,
+    /// The author and owner of the save.
+    pub author: User,
+}
+
+impl Default for VoxelImportConfig {
+    fn default() -> Self {
+        VoxelImportConfig {
+            asset: "PB_DefaultBrick".into(),
+            material: "BMC_Plastic".into(),
+            merge: false,
+            author: User::default(),
+        }
+    }
+}
+
+/// A report of the changes made by [`SaveData::repair_indices`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// Every change made, in the order it was made.
+    pub changes: Vec<Repair>,
+}
+
+impl RepairReport {
+    /// Whether or not no changes were made.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// The number of entries removed by [`SaveData::compact`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactReport {
+    /// Components applied to no bricks.
+    pub components: usize,
+    /// Unused colors in `header2`.
+    pub colors: usize,
+    /// Unused materials in `header2`.
+    pub materials: usize,
+    /// Unused physical materials in `header2`.
+    pub physical_materials: usize,
+}
+
+/// The number of bricks and entries removed by [`SaveData::cleanup`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    /// Invisible bricks.
+    pub invisible_bricks: usize,
+    /// Procedural bricks with a size of zero.
+    pub zero_size_bricks: usize,
+    /// Entries removed by [`SaveData::compact`].
+    pub compact: CompactReport,
+}
+
+/// A summary of the bricks of one brick owner, from [`SaveData::brick_ownership_report`].
+#[cfg(feature = "util")]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct OwnerReport {
+    /// The owner, as it is in `header2`. Public bricks use an owner named `PUBLIC` with a nil ID.
+    pub owner: BrickOwner,
+    /// The number of bricks the owner has in the save.
+    pub brick_count: u32,
+    /// The number of the owner's bricks using each brick asset.
+    pub asset_counts: HashMap<String, u32>,
+    /// The number of the owner's bricks using each material.
+    pub material_counts: HashMap<String, u32>,
+    /// Whether or not any of the owner's bricks have a component.
+    pub has_components: bool,
+    /// The bounds of the owner's bricks, in the same form as [`SaveData::bounds`], or `None`
+    /// if the owner has no bricks.
+    pub bounds: Option<(i32, i32, i32, i32, i32, i32)>,
+}
+
+#[cfg(feature = "util")]
+impl OwnerReport {
+    fn new(owner: BrickOwner) -> Self {
+        OwnerReport {
+            owner,
+            brick_count: 0,
+            asset_counts: HashMap::new(),
+            material_counts: HashMap::new(),
+            has_components: false,
+            bounds: None,
+        }
     }
 }
 
+/// An error from adding a material with [`Header2::add_material`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MaterialError {
+    #[error("invalid material name: {0:?}")]
+    InvalidName(String),
+}
+
+/// An error from combining saves with [`SaveData::merge`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MergeError {
+    #[error("component {0:?} has different properties in each save")]
+    ComponentPropertyMismatch(String),
+}
+
+/// An error from transforming a save with [`SaveData::apply_transform`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransformError {
+    #[error("transform does not rotate to an axis-aligned orientation")]
+    UnsupportedRotation,
+    #[error("brick {0} would not be at a whole number position")]
+    NonIntegerPosition(usize),
+}
+
+/// An error from scaling a save with [`SaveData::scale_xyz`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleError {
+    #[error("brick {0} would have a zero or negative size")]
+    NonPositiveSize(usize),
+}
+
+/// An error from reading a save with [`SaveData::import_from_json_lines`].
+#[cfg(feature = "serialize")]
+#[derive(Error, Debug)]
+pub enum JsonLinesError {
+    #[error("generic io error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("invalid json on line {line}: {source}")]
+    InvalidJson {
+        line: usize,
+        source: serde_json::Error,
+    },
+    #[error("missing header line")]
+    MissingHeader,
+}
+
+/// An error from moving a component with [`SaveData::transfer_component`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ComponentTransferError {
+    #[error("component {0:?} is not in the source save")]
+    MissingComponent(String),
+    #[error("component {0:?} is already in the target save")]
+    ComponentExists(String),
+    #[error("brick index {0} is out of range for the target save")]
+    BrickOutOfRange(usize),
+}
+
+/// A single change made by [`SaveData::repair_indices`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair {
+    /// A brick's asset name index was clamped.
+    AssetIndex { brick: usize, from: u32, to: u32 },
+    /// A brick's material index was clamped.
+    MaterialIndex { brick: usize, from: u32, to: u32 },
+    /// A brick's physical material index was clamped.
+    PhysicalIndex { brick: usize, from: u32, to: u32 },
+    /// A brick's color index was clamped.
+    ColorIndex { brick: usize, from: u32, to: u32 },
+    /// An out of range brick index was removed from a component.
+    ComponentBrickIndex { component: String, index: u32 },
+    /// A component was removed from a brick that its component did not list.
+    BrickComponent { brick: usize, component: String },
+}
+
 impl Default for SaveData {
     fn default() -> Self {
         SaveData {
@@ -81,6 +2703,48 @@ impl Default for SaveData {
     }
 }
 
+impl fmt::Display for SaveData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "SaveData v{} on {}: {} bricks, {} assets, {} owners",
+            self.version,
+            self.header1.map,
+            self.bricks.len(),
+            self.header2.brick_assets.len(),
+            self.header2.brick_owners.len()
+        )
+    }
+}
+
+impl IntoIterator for SaveData {
+    type Item = Brick;
+    type IntoIter = std::vec::IntoIter<Brick>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.bricks.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a SaveData {
+    type Item = &'a Brick;
+    type IntoIter = std::slice::Iter<'a, Brick>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.bricks.iter()
+    }
+}
+
+impl Extend<Brick> for SaveData {
+    /// Append bricks to the save, updating `header1.brick_count`.
+    ///
+    /// Brick owner counts are not updated; use [`SaveData::normalize_owners`] for that.
+    fn extend<T: IntoIterator<Item = Brick>>(&mut self, iter: T) {
+        self.bricks.extend(iter);
+        self.repair_brick_count();
+    }
+}
+
 /// The first header in a save file. Contains basic save information.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize), serde(default))]
@@ -117,6 +2781,53 @@ impl Default for Header1 {
     }
 }
 
+// users are compared with `eq_full`, so a renamed author or host is a different header
+impl PartialEq for Header1 {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+            && self.description == other.description
+            && self.author.eq_full(&other.author)
+            && match (&self.host, &other.host) {
+                (Some(a), Some(b)) => a.eq_full(b),
+                (a, b) => a.is_none() && b.is_none(),
+            }
+            && self.save_time == other.save_time
+            && self.brick_count == other.brick_count
+    }
+}
+
+impl Eq for Header1 {}
+
+impl Header1 {
+    /// Rename the author of the save.
+    pub fn rename_author(&mut self, new_name: &str) {
+        self.author.name = new_name.into();
+    }
+
+    /// Rename the host of the save, returning whether or not the save has a host.
+    pub fn rename_host(&mut self, new_name: &str) -> bool {
+        match &mut self.host {
+            Some(host) => {
+                host.name = new_name.into();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for Header1 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} by {}: {} bricks",
+            self.map,
+            self.author.display_name(),
+            self.brick_count
+        )
+    }
+}
+
 /// The second header in a save file. Contains universal brick metadata.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize), serde(default))]
@@ -138,6 +2849,9 @@ pub struct Header2 {
 
     /// A list of physical materials. Possibly empty, if the game version is too old.
     pub physical_materials: Vec<String>,
+
+    /// A list of texture assets. Only read from saves of version 11 or newer, and never written.
+    pub texture_assets: Vec<String>,
 }
 
 impl Default for Header2 {
@@ -149,12 +2863,127 @@ impl Default for Header2 {
             materials: vec!["BMC_Plastic".into()],
             brick_owners: vec![],
             physical_materials: vec!["BPMC_Default".into()],
+            texture_assets: vec![],
+        }
+    }
+}
+
+// brick owners are compared with `eq_full`, so renamed owners or changed brick counts make
+// a different header
+impl PartialEq for Header2 {
+    fn eq(&self, other: &Self) -> bool {
+        self.mods == other.mods
+            && self.brick_assets == other.brick_assets
+            && self.colors == other.colors
+            && self.materials == other.materials
+            && self.brick_owners.len() == other.brick_owners.len()
+            && self
+                .brick_owners
+                .iter()
+                .zip(&other.brick_owners)
+                .all(|(a, b)| a.eq_full(b))
+            && self.physical_materials == other.physical_materials
+            && self.texture_assets == other.texture_assets
+    }
+}
+
+impl Eq for Header2 {}
+
+impl Header2 {
+    /// Create a header with the given asset, material, and physical material lists, and no
+    /// mods, colors, or brick owners.
+    pub fn with_assets(
+        brick_assets: Vec<String>,
+        materials: Vec<String>,
+        physical_materials: Vec<String>,
+    ) -> Self {
+        Header2 {
+            mods: vec![],
+            brick_assets,
+            colors: vec![],
+            materials,
+            brick_owners: vec![],
+            physical_materials,
+            texture_assets: vec![],
+        }
+    }
+
+    /// Create a header identical to that of `other`.
+    pub fn from_save(other: &SaveData) -> Self {
+        other.header2.clone()
+    }
+
+    /// Get the index of the material `name`, adding it to the end of `materials` if it isn't
+    /// there yet. Material names must be non-empty and only contain ASCII letters, digits,
+    /// and underscores.
+    pub fn add_material(&mut self, name: &str) -> Result<u32, MaterialError> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(MaterialError::InvalidName(name.to_owned()));
+        }
+
+        Ok(match self.materials.iter().position(|m| m == name) {
+            Some(i) => i as u32,
+            None => {
+                self.materials.push(name.to_owned());
+                self.materials.len() as u32 - 1
+            }
+        })
+    }
+
+    /// Reorder `colors` so that the colors used by the most bricks come first, updating
+    /// the color indices of `bricks` to match. Ties keep their original order.
+    pub fn sort_colors_by_frequency(&mut self, bricks: &mut [Brick]) {
+        let mut counts = vec![0usize; self.colors.len()];
+        for brick in bricks.iter() {
+            if let BrickColor::Index(index) = brick.color {
+                if let Some(count) = counts.get_mut(index as usize) {
+                    *count += 1;
+                }
+            }
+        }
+
+        // order[new] = old
+        let mut order = (0..self.colors.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| std::cmp::Reverse(counts[i]));
+
+        let mut old_to_new = vec![0u32; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            old_to_new[old] = new as u32;
         }
+
+        let mut colors = std::mem::take(&mut self.colors)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        self.colors = order.iter().filter_map(|&i| colors[i].take()).collect();
+
+        for brick in bricks.iter_mut() {
+            if let BrickColor::Index(index) = &mut brick.color {
+                if let Some(&new) = old_to_new.get(*index as usize) {
+                    *index = new;
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Header2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} mods, {} assets, {} colors, {} materials, {} physical materials, {} owners",
+            self.mods.len(),
+            self.brick_assets.len(),
+            self.colors.len(),
+            self.materials.len(),
+            self.physical_materials.len(),
+            self.brick_owners.len()
+        )
     }
 }
 
 /// An image preview embedded in a save, represented by its bytes.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Preview {
     /// No preview.
     None,
@@ -175,9 +3004,12 @@ impl Preview {
     pub fn from_reader(r: &mut impl Read) -> Result<Self, ReadError> {
         fn read_bytes(r: &mut impl Read) -> Result<Vec<u8>, ReadError> {
             let len = r.read_i32::<LittleEndian>()?;
-            let mut vec = vec![0u8; len as usize];
-            r.read_exact(&mut vec)?;
-            Ok(vec)
+            if len < 0 {
+                return Err(
+                    io::Error::new(io::ErrorKind::InvalidData, "invalid preview length").into(),
+                );
+            }
+            Ok(r.read_byte_vec(len as usize)?)
         }
 
         let mode = r.read_u8()?;
@@ -189,6 +3021,44 @@ impl Preview {
         })
     }
 
+    /// Load a PNG preview from the file at `path`.
+    pub fn load_png(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Preview::PNG(std::fs::read(path)?))
+    }
+
+    /// Load a JPEG preview from the file at `path`.
+    pub fn load_jpeg(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Preview::JPEG(std::fs::read(path)?))
+    }
+
+    /// Write the preview's bytes to the file at `path`, replacing its extension with `.png`
+    /// or `.jpeg` for PNG and JPEG previews. Fails if no preview was set.
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let (path, bytes) = match self {
+            Preview::None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "no preview set",
+                ))
+            }
+            Preview::PNG(bytes) => (path.with_extension("png"), bytes),
+            Preview::JPEG(bytes) => (path.with_extension("jpeg"), bytes),
+            Preview::Unknown(_, bytes) => (path.to_path_buf(), bytes),
+        };
+        std::fs::write(path, bytes)
+    }
+
+    /// Whether or not this is a PNG preview whose bytes start with the PNG signature.
+    pub fn is_valid_png(&self) -> bool {
+        matches!(self, Preview::PNG(bytes) if bytes.starts_with(b"\x89PNG\r\n\x1a\n"))
+    }
+
+    /// Whether or not this is a JPEG preview whose bytes start with a JPEG marker.
+    pub fn is_valid_jpeg(&self) -> bool {
+        matches!(self, Preview::JPEG(bytes) if bytes.starts_with(&[0xFF, 0xD8, 0xFF]))
+    }
+
     pub fn type_byte(&self) -> u8 {
         match self {
             Preview::None => 0,
@@ -222,6 +3092,56 @@ impl Preview {
     pub fn unwrap(self) -> Vec<u8> {
         self.into_bytes().unwrap()
     }
+
+    /// Read the `(width, height)` of the preview image from its header, without decoding it.
+    /// Returns `None` for unset or unknown previews, or if the header is malformed.
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        fn be_u32(bytes: &[u8]) -> u32 {
+            u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        }
+
+        fn be_u16(bytes: &[u8]) -> u16 {
+            u16::from_be_bytes([bytes[0], bytes[1]])
+        }
+
+        match self {
+            Preview::PNG(bytes) => {
+                // the IHDR chunk always comes first, right after the 8 byte signature
+                if bytes.len() < 24 || &bytes[12..16] != b"IHDR" {
+                    return None;
+                }
+                Some((be_u32(&bytes[16..20]), be_u32(&bytes[20..24])))
+            }
+            Preview::JPEG(bytes) => {
+                if bytes.get(0..2)? != [0xFF, 0xD8] {
+                    return None;
+                }
+
+                // walk the segments until a start of frame marker
+                let mut i = 2;
+                loop {
+                    if *bytes.get(i)? != 0xFF {
+                        return None;
+                    }
+                    let marker = *bytes.get(i + 1)?;
+                    match marker {
+                        0xFF => i += 1,
+                        0x01 | 0xD0..=0xD7 => i += 2,
+                        0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                            let frame = bytes.get(i + 5..i + 9)?;
+                            return Some((
+                                be_u16(&frame[2..4]) as u32,
+                                be_u16(&frame[0..2]) as u32,
+                            ));
+                        }
+                        0xD9 | 0xDA => return None,
+                        _ => i += 2 + be_u16(bytes.get(i + 2..i + 4)?) as usize,
+                    }
+                }
+            }
+            Preview::None | Preview::Unknown(..) => None,
+        }
+    }
 }
 
 /// An Unreal type, used as values to fields in components.
@@ -233,11 +3153,46 @@ pub enum UnrealType {
     Boolean(bool),
     Float(f32),
     Color(Color),
+    /// A linear-space color as `(r, g, b, a)`, which may go above 1.
+    LinearColor(f32, f32, f32, f32),
     Byte(u8),
     Rotator(f32, f32, f32),
 }
 
-/// A user.
+impl fmt::Display for UnrealType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnrealType::Class(s) | UnrealType::String(s) => write!(f, "{}", s),
+            UnrealType::Boolean(b) => write!(f, "{}", b),
+            UnrealType::Float(n) => write!(f, "{:.6}", n),
+            UnrealType::Color(c) => write!(f, "rgba({}, {}, {}, {})", c.r, c.g, c.b, c.a),
+            UnrealType::LinearColor(r, g, b, a) => {
+                write!(f, "linear({}, {}, {}, {})", r, g, b, a)
+            }
+            UnrealType::Byte(b) => write!(f, "{:#04x}", b),
+            UnrealType::Rotator(pitch, yaw, roll) => {
+                write!(f, "(pitch={}, yaw={}, roll={})", pitch, yaw, roll)
+            }
+        }
+    }
+}
+
+impl UnrealType {
+    /// Format the value for display. Equivalent to `to_string()`.
+    pub fn to_display_string(&self) -> String {
+        self.to_string()
+    }
+
+    /// Get the `(r, g, b, a)` of a linear color, or `None` for any other type.
+    pub fn try_as_linear_color(&self) -> Option<(f32, f32, f32, f32)> {
+        match *self {
+            UnrealType::LinearColor(r, g, b, a) => Some((r, g, b, a)),
+            _ => None,
+        }
+    }
+}
+
+/// A user. Users are equal if their IDs are equal, regardless of name.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize), serde(default))]
 pub struct User {
@@ -257,7 +3212,41 @@ impl Default for User {
     }
 }
 
+impl User {
+    /// Format the user as `name (id)`.
+    pub fn display_name(&self) -> String {
+        format!("{} ({})", self.name, self.id)
+    }
+
+    /// Whether or not the user's ID is nil, as it is for the default (unknown) user.
+    #[inline]
+    pub fn is_nil(&self) -> bool {
+        self.id.is_nil()
+    }
+
+    /// Whether or not both the name and ID of the user are equal to those of `other`.
+    pub fn eq_full(&self, other: &User) -> bool {
+        self.id == other.id && self.name == other.name
+    }
+}
+
+impl PartialEq for User {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for User {}
+
+impl Hash for User {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
 /// A brick owner. Similar to a [`User`](User), but stores a `u32` representing bricks in save.
+///
+/// Like users, brick owners are equal if their IDs are equal.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct BrickOwner {
@@ -287,10 +3276,49 @@ impl BrickOwner {
             bricks,
         }
     }
+
+    /// Whether or not the name, ID, and brick count of the owner are all equal to those of
+    /// `other`.
+    pub fn eq_full(&self, other: &BrickOwner) -> bool {
+        self.id == other.id && self.name == other.name && self.bricks == other.bricks
+    }
+
+    /// Format the owner as `name (id)`.
+    pub fn display_name(&self) -> String {
+        format!("{} ({})", self.name, self.id)
+    }
+
+    /// The last 8 hex characters of the owner's ID, for compact display.
+    pub fn short_id(&self) -> String {
+        let id = self.id.to_string();
+        id[id.len() - 8..].to_owned()
+    }
+
+    /// Convert the owner back into a [`User`], dropping its brick count.
+    pub fn as_user(&self) -> User {
+        User {
+            name: self.name.clone(),
+            id: self.id,
+        }
+    }
 }
 
-/// A color, in RGBA.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+impl PartialEq for BrickOwner {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for BrickOwner {}
+
+impl Hash for BrickOwner {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+/// A color, in RGBA. Colors are ordered by `r`, then `g`, `b`, and `a`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -367,10 +3395,213 @@ impl Color {
             a: 255,
         }
     }
+
+    /// Converts a packed `0xRRGGBBAA` integer to a Color.
+    pub fn from_u32_rgba(v: u32) -> Self {
+        let [r, g, b, a] = v.to_be_bytes();
+        Color { r, g, b, a }
+    }
+
+    /// Converts this Color to a packed `0xRRGGBBAA` integer.
+    pub fn to_u32_rgba(&self) -> u32 {
+        u32::from_be_bytes([self.r, self.g, self.b, self.a])
+    }
+
+    /// Converts a packed `0xAARRGGBB` integer to a Color.
+    pub fn from_u32_argb(v: u32) -> Self {
+        let [a, r, g, b] = v.to_be_bytes();
+        Color { r, g, b, a }
+    }
+
+    /// Converts this Color to a packed `0xAARRGGBB` integer.
+    pub fn to_u32_argb(&self) -> u32 {
+        u32::from_be_bytes([self.a, self.r, self.g, self.b])
+    }
+
+    /// Converts a packed `0xBBGGRRAA` integer to a Color.
+    pub fn from_u32_bgra(v: u32) -> Self {
+        let [b, g, r, a] = v.to_be_bytes();
+        Color { r, g, b, a }
+    }
+
+    /// Converts this Color to a packed `0xBBGGRRAA` integer.
+    pub fn to_u32_bgra(&self) -> u32 {
+        u32::from_be_bytes([self.b, self.g, self.r, self.a])
+    }
+
+    /// Formats this Color as a `#rrggbbaa` hex string.
+    pub fn to_hex(&self) -> String {
+        format!("#{:08x}", self.to_u32_rgba())
+    }
+
+    /// The Rec. 709 luma of this Color, from 0 to 1.
+    pub fn luminance(&self) -> f32 {
+        (0.2126 * self.r as f32 + 0.7152 * self.g as f32 + 0.0722 * self.b as f32) / 255.0
+    }
+
+    /// The HSV hue of this Color in degrees, from 0 up to 360. Grays have a hue of 0.
+    pub fn hue(&self) -> f32 {
+        let (r, g, b) = (self.r as f32, self.g as f32, self.b as f32);
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+        if delta == 0.0 {
+            return 0.0;
+        }
+
+        let hue = if max == r {
+            (g - b) / delta
+        } else if max == g {
+            (b - r) / delta + 2.0
+        } else {
+            (r - g) / delta + 4.0
+        } * 60.0;
+        if hue < 0.0 {
+            hue + 360.0
+        } else {
+            hue
+        }
+    }
+
+    /// Convert this Color to HSL, as `(h, s, l)` with `h` in degrees from 0 up to 360 and `s`
+    /// and `l` from 0 to 1. Alpha is ignored.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (r, g, b) = (
+            self.r as f32 / 255.0,
+            self.g as f32 / 255.0,
+            self.b as f32 / 255.0,
+        );
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let l = (max + min) / 2.0;
+        let s = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        (self.hue(), s.min(1.0), l)
+    }
+
+    /// Convert an HSL color to a Color with alpha `a`. `h` is in degrees and wraps around,
+    /// and `s` and `l` are clamped from 0 to 1.
+    pub fn from_hsl(h: f32, s: f32, l: f32, a: u8) -> Color {
+        let (s, l) = (s.clamp(0.0, 1.0), l.clamp(0.0, 1.0));
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let h = h.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = l - chroma / 2.0;
+        let byte = |c: f32| ((c + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Color {
+            r: byte(r),
+            g: byte(g),
+            b: byte(b),
+            a,
+        }
+    }
+
+    /// Format this Color as a CSS `hsl(H, S%, L%)` string, ignoring alpha.
+    pub fn to_hsl_string(&self) -> String {
+        let (h, s, l) = self.to_hsl();
+        format!("hsl({:.0}, {:.0}%, {:.0}%)", h, s * 100.0, l * 100.0)
+    }
+
+    /// The Euclidean distance between this Color and `other` in RGB space, ignoring alpha.
+    pub fn distance_to_euclidean(&self, other: &Color) -> f32 {
+        let dr = self.r as f32 - other.r as f32;
+        let dg = self.g as f32 - other.g as f32;
+        let db = self.b as f32 - other.b as f32;
+        (dr * dr + dg * dg + db * db).sqrt()
+    }
+
+    /// The Euclidean distance between this Color and `other` in RGBA space.
+    pub fn distance_to_rgba(&self, other: &Color) -> f32 {
+        let da = self.a as f32 - other.a as f32;
+        let rgb = self.distance_to_euclidean(other);
+        (rgb * rgb + da * da).sqrt()
+    }
+
+    /// The CIE76 distance between this Color and `other`, the Euclidean distance between
+    /// them in CIE L\*a\*b\* space under the D65 illuminant. Alpha is ignored.
+    pub fn distance_to_cie76(&self, other: &Color) -> f32 {
+        let (l1, a1, b1) = self.to_lab();
+        let (l2, a2, b2) = other.to_lab();
+        ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+    }
+
+    /// Find the entry in `palette` nearest to this Color by RGBA distance, preferring the
+    /// first of any ties, or `None` if `palette` is empty.
+    pub fn nearest_in_palette<'a>(&self, palette: &'a [Color]) -> Option<(usize, &'a Color)> {
+        palette
+            .iter()
+            .enumerate()
+            .map(|(i, color)| (i, color, self.distance_to_rgba(color)))
+            .fold(
+                None,
+                |best: Option<(usize, &Color, f32)>, entry| match best {
+                    Some(best) if best.2 <= entry.2 => Some(best),
+                    _ => Some(entry),
+                },
+            )
+            .map(|(i, color, _)| (i, color))
+    }
+
+    /// Convert this Color from sRGB to CIE L\*a\*b\* under the D65 illuminant.
+    fn to_lab(&self) -> (f32, f32, f32) {
+        let (r, g, b) = (
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+        );
+        let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / 0.95047;
+        let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+        let z = (0.0193339 * r + 0.119192 * g + 0.9503041 * b) / 1.08883;
+
+        let f = |t: f32| {
+            const DELTA: f32 = 6.0 / 29.0;
+            if t > DELTA * DELTA * DELTA {
+                t.cbrt()
+            } else {
+                t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+            }
+        };
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+        (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+    }
+}
+
+/// Convert an sRGB byte to a linear value in `[0, 1]`.
+pub(crate) fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl From<u32> for Color {
+    /// Equivalent to [`Color::from_u32_rgba`].
+    fn from(v: u32) -> Self {
+        Color::from_u32_rgba(v)
+    }
+}
+
+impl From<Color> for u32 {
+    /// Equivalent to [`Color::to_u32_rgba`].
+    fn from(color: Color) -> Self {
+        color.to_u32_rgba()
+    }
 }
 
 /// A brick.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize), serde(default))]
 pub struct Brick {
     /// The asset name index of the brick, referring to `Header2`'s `brick_assets`.
@@ -414,6 +3645,8 @@ pub struct Brick {
     pub components: HashMap<String, HashMap<String, UnrealType>>,
 }
 
+// kept as a bare index or an `[r, g, b]` array rather than the `Display` string, to match
+// the brick color format of brs-js JSON saves
 #[cfg(feature = "serialize")]
 fn brick_color_serialize<S: Serializer>(color: &BrickColor, s: S) -> Result<S::Ok, S::Error> {
     match color {
@@ -448,6 +3681,174 @@ impl Default for Brick {
     }
 }
 
+impl Brick {
+    /// Whether or not the brick is procedural (has a `Size::Procedural` size).
+    #[inline]
+    pub fn is_procedural(&self) -> bool {
+        matches!(self.size, Size::Procedural(..))
+    }
+
+    /// Whether or not the brick is owned by PUBLIC.
+    #[inline]
+    pub fn is_public(&self) -> bool {
+        self.owner_index == 0
+    }
+
+    /// Whether or not the brick is visible.
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.visibility
+    }
+
+    /// The procedural size of the brick, or `(1, 1, 1)` for non-procedural bricks.
+    #[inline]
+    pub fn size_or_default(&self) -> (u32, u32, u32) {
+        match self.size {
+            Size::Procedural(x, y, z) => (x, y, z),
+            Size::Empty => (1, 1, 1),
+        }
+    }
+
+    /// The product of the components of [`size_or_default`](Brick::size_or_default),
+    /// saturating at `u64::MAX`.
+    #[inline]
+    pub fn volume(&self) -> u64 {
+        let (x, y, z) = self.size_or_default();
+        Size::Procedural(x, y, z).volume()
+    }
+
+    /// Look up the name of the brick's asset in `header2`.
+    #[inline]
+    pub fn asset_name<'a>(&self, header2: &'a Header2) -> Option<&'a str> {
+        header2
+            .brick_assets
+            .get(self.asset_name_index as usize)
+            .map(String::as_str)
+    }
+
+    /// Attach the component `name` with the properties `props` to the brick, replacing any
+    /// existing properties for it.
+    pub fn with_component(
+        mut self,
+        name: impl Into<String>,
+        props: HashMap<String, UnrealType>,
+    ) -> Self {
+        self.components.insert(name.into(), props);
+        self
+    }
+
+    /// Set the property `prop` of the component `component` on the brick, attaching the
+    /// component if it is absent.
+    pub fn add_component_property(&mut self, component: &str, prop: &str, value: UnrealType) {
+        self.components
+            .entry(component.into())
+            .or_default()
+            .insert(prop.into(), value);
+    }
+
+    /// Whether or not the brick has the component `name` with at least one property.
+    pub fn has_component(&self, name: &str) -> bool {
+        self.components.get(name).is_some_and(|m| !m.is_empty())
+    }
+
+    /// Get the properties of the component `name` on the brick.
+    #[inline]
+    pub fn get_component(&self, name: &str) -> Option<&HashMap<String, UnrealType>> {
+        self.components.get(name)
+    }
+
+    /// The row-major 3x3 rotation matrix of the brick's direction and rotation.
+    #[inline]
+    pub fn orientation_matrix(&self) -> [[f32; 3]; 3] {
+        ORIENTATION_MATRICES[((self.direction as usize) << 2) | self.rotation as usize]
+    }
+
+    /// The brick's orientation as a unit quaternion, in `(x, y, z, w)` order.
+    pub fn orientation_quaternion(&self) -> [f32; 4] {
+        let m = self.orientation_matrix();
+        let trace = m[0][0] + m[1][1] + m[2][2];
+
+        if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            [
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+                s / 4.0,
+            ]
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+            [
+                s / 4.0,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[2][1] - m[1][2]) / s,
+            ]
+        } else if m[1][1] > m[2][2] {
+            let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+            [
+                (m[0][1] + m[1][0]) / s,
+                s / 4.0,
+                (m[1][2] + m[2][1]) / s,
+                (m[0][2] - m[2][0]) / s,
+            ]
+        } else {
+            let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+            [
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                s / 4.0,
+                (m[1][0] - m[0][1]) / s,
+            ]
+        }
+    }
+}
+
+/// Find the direction and rotation whose orientation matrix is `m`, if there is one.
+fn rotation_matrix_to_orientation(m: &[[f32; 3]; 3]) -> Option<(Direction, Rotation)> {
+    let i = ORIENTATION_MATRICES.iter().position(|o| {
+        o.iter()
+            .flatten()
+            .zip(m.iter().flatten())
+            .all(|(a, b)| (a - b).abs() < 1e-3)
+    })?;
+    Some((
+        Direction::try_from(i as u8 >> 2).ok()?,
+        Rotation::try_from(i as u8 & 3).ok()?,
+    ))
+}
+
+/// The rotation matrix of every orientation, indexed by `(direction << 2) | rotation`.
+///
+/// Matrices are row-major and map a brick's local axes to world axes.
+#[rustfmt::skip]
+static ORIENTATION_MATRICES: [[[f32; 3]; 3]; 24] = [
+    [[ 0.0,  0.0,  1.0], [ 0.0, -1.0,  0.0], [ 1.0,  0.0,  0.0]],
+    [[ 0.0,  0.0,  1.0], [-1.0,  0.0,  0.0], [ 0.0, -1.0,  0.0]],
+    [[ 0.0,  0.0,  1.0], [ 0.0,  1.0,  0.0], [-1.0,  0.0,  0.0]],
+    [[ 0.0,  0.0,  1.0], [ 1.0,  0.0,  0.0], [ 0.0,  1.0,  0.0]],
+    [[ 0.0,  0.0, -1.0], [ 0.0,  1.0,  0.0], [ 1.0,  0.0,  0.0]],
+    [[ 0.0,  0.0, -1.0], [ 1.0,  0.0,  0.0], [ 0.0, -1.0,  0.0]],
+    [[ 0.0,  0.0, -1.0], [ 0.0, -1.0,  0.0], [-1.0,  0.0,  0.0]],
+    [[ 0.0,  0.0, -1.0], [-1.0,  0.0,  0.0], [ 0.0,  1.0,  0.0]],
+    [[ 0.0,  1.0,  0.0], [ 0.0,  0.0,  1.0], [ 1.0,  0.0,  0.0]],
+    [[ 1.0,  0.0,  0.0], [ 0.0,  0.0,  1.0], [ 0.0, -1.0,  0.0]],
+    [[ 0.0, -1.0,  0.0], [ 0.0,  0.0,  1.0], [-1.0,  0.0,  0.0]],
+    [[-1.0,  0.0,  0.0], [ 0.0,  0.0,  1.0], [ 0.0,  1.0,  0.0]],
+    [[ 0.0, -1.0,  0.0], [ 0.0,  0.0, -1.0], [ 1.0,  0.0,  0.0]],
+    [[-1.0,  0.0,  0.0], [ 0.0,  0.0, -1.0], [ 0.0, -1.0,  0.0]],
+    [[ 0.0,  1.0,  0.0], [ 0.0,  0.0, -1.0], [-1.0,  0.0,  0.0]],
+    [[ 1.0,  0.0,  0.0], [ 0.0,  0.0, -1.0], [ 0.0,  1.0,  0.0]],
+    [[ 1.0,  0.0,  0.0], [ 0.0,  1.0,  0.0], [ 0.0,  0.0,  1.0]],
+    [[ 0.0, -1.0,  0.0], [ 1.0,  0.0,  0.0], [ 0.0,  0.0,  1.0]],
+    [[-1.0,  0.0,  0.0], [ 0.0, -1.0,  0.0], [ 0.0,  0.0,  1.0]],
+    [[ 0.0,  1.0,  0.0], [-1.0,  0.0,  0.0], [ 0.0,  0.0,  1.0]],
+    [[-1.0,  0.0,  0.0], [ 0.0,  1.0,  0.0], [ 0.0,  0.0, -1.0]],
+    [[ 0.0,  1.0,  0.0], [ 1.0,  0.0,  0.0], [ 0.0,  0.0, -1.0]],
+    [[ 1.0,  0.0,  0.0], [ 0.0, -1.0,  0.0], [ 0.0,  0.0, -1.0]],
+    [[ 0.0, -1.0,  0.0], [-1.0,  0.0,  0.0], [ 0.0,  0.0, -1.0]],
+];
+
 // Manual Hash impl necessary as `HashMap` is not `Hash`.
 impl Hash for Brick {
     fn hash<H: Hasher>(&self, state: &mut H) {
@@ -466,6 +3867,17 @@ impl Hash for Brick {
     }
 }
 
+impl fmt::Display for Brick {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (x, y, z) = self.position;
+        write!(
+            f,
+            "Brick at ({}, {}, {}), asset#{}, material#{}, color={}, owner#{}",
+            x, y, z, self.asset_name_index, self.material_index, self.color, self.owner_index
+        )
+    }
+}
+
 /// Represents a brick's direction.
 #[repr(u8)]
 #[derive(
@@ -494,6 +3906,85 @@ pub enum Rotation {
     Deg270,
 }
 
+impl Direction {
+    /// Get the direction resulting from rotating this direction around the Z axis by `rotation`.
+    pub fn rotate_by(self, rotation: Rotation) -> Direction {
+        self.rotate_around(Direction::ZPositive, rotation)
+    }
+
+    /// Get the direction resulting from rotating this direction counter-clockwise around
+    /// `axis` by `rotation`, following the right-hand rule.
+    pub fn rotate_around(self, axis: Direction, rotation: Rotation) -> Direction {
+        let a = axis.unit_vector();
+        let mut v = self.unit_vector();
+        for _ in 0..rotation as u8 {
+            // rodrigues' rotation formula at 90 degrees: (a . v) a + a x v
+            let dot = a.0 * v.0 + a.1 * v.1 + a.2 * v.2;
+            v = (
+                dot * a.0 + a.1 * v.2 - a.2 * v.1,
+                dot * a.1 + a.2 * v.0 - a.0 * v.2,
+                dot * a.2 + a.0 * v.1 - a.1 * v.0,
+            );
+        }
+        Direction::from_unit_vector(v)
+    }
+
+    fn unit_vector(self) -> (i32, i32, i32) {
+        match self {
+            Direction::XPositive => (1, 0, 0),
+            Direction::XNegative => (-1, 0, 0),
+            Direction::YPositive => (0, 1, 0),
+            Direction::YNegative => (0, -1, 0),
+            Direction::ZPositive => (0, 0, 1),
+            Direction::ZNegative => (0, 0, -1),
+        }
+    }
+
+    fn from_unit_vector(v: (i32, i32, i32)) -> Direction {
+        match v {
+            (1, 0, 0) => Direction::XPositive,
+            (-1, 0, 0) => Direction::XNegative,
+            (0, 1, 0) => Direction::YPositive,
+            (0, -1, 0) => Direction::YNegative,
+            (0, 0, 1) => Direction::ZPositive,
+            _ => Direction::ZNegative,
+        }
+    }
+}
+
+impl Rotation {
+    /// Get the rotation equivalent to applying this rotation, then `other`.
+    pub fn combine(self, other: Rotation) -> Rotation {
+        Rotation::try_from((self as u8 + other as u8) % 4).unwrap()
+    }
+}
+
+/// An axis in 3D space.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    /// Get the coordinate of `point` along this axis.
+    pub fn coordinate(self, point: (i32, i32, i32)) -> i32 {
+        match self {
+            Axis::X => point.0,
+            Axis::Y => point.1,
+            Axis::Z => point.2,
+        }
+    }
+}
+
+/// Which end of a range to select.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Extremal {
+    Min,
+    Max,
+}
+
 /// Represents a storable brick size.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Size {
@@ -504,6 +3995,81 @@ pub enum Size {
     Procedural(u32, u32, u32),
 }
 
+impl Size {
+    /// The volume of the size, `x * y * z`, saturating at `u64::MAX`. `Size::Empty` has a
+    /// volume of 0.
+    pub fn volume(&self) -> u64 {
+        let (x, y, z) = self.dimensions();
+        (x as u64 * y as u64).saturating_mul(z as u64)
+    }
+
+    /// The surface area of the size, `2 * (x * y + y * z + z * x)`, saturating at `u64::MAX`.
+    /// `Size::Empty` has a surface area of 0.
+    pub fn surface_area(&self) -> u64 {
+        let (x, y, z) = self.dimensions();
+        let (x, y, z) = (x as u64, y as u64, z as u64);
+        (x * y)
+            .saturating_add(y * z)
+            .saturating_add(z * x)
+            .saturating_mul(2)
+    }
+
+    /// The largest of the size's axes. `Size::Empty` has a max dimension of 0.
+    pub fn max_dimension(&self) -> u32 {
+        let (x, y, z) = self.dimensions();
+        x.max(y).max(z)
+    }
+
+    /// Whether or not every axis of the size is the same. `Size::Empty` is treated as 0x0x0.
+    pub fn is_cube(&self) -> bool {
+        let (x, y, z) = self.dimensions();
+        x == y && y == z
+    }
+
+    /// Get a procedural size with axis `axis` (0, 1, or 2 for X, Y, or Z) set to `value`.
+    /// `Size::Empty` is treated as 0x0x0.
+    ///
+    /// Panics if `axis` is greater than 2.
+    pub fn with_axis(&self, axis: usize, value: u32) -> Size {
+        let (x, y, z) = self.dimensions();
+        match axis {
+            0 => Size::Procedural(value, y, z),
+            1 => Size::Procedural(x, value, z),
+            2 => Size::Procedural(x, y, value),
+            _ => panic!("invalid axis {}", axis),
+        }
+    }
+
+    /// Swap the X and Y axes of a procedural size. `Size::Empty` is returned as-is.
+    pub fn swap_xy(self) -> Size {
+        match self {
+            Size::Procedural(x, y, z) => Size::Procedural(y, x, z),
+            Size::Empty => Size::Empty,
+        }
+    }
+
+    /// Get the world-space size of a procedural size on a brick oriented with `dir` and `rot`,
+    /// permuting its axes. `Size::Empty` is returned as-is.
+    pub fn apply_rotation(self, dir: Direction, rot: Rotation) -> Size {
+        match self {
+            Size::Procedural(x, y, z) => {
+                let local = [x, y, z];
+                let m = &ORIENTATION_MATRICES[((dir as usize) << 2) | rot as usize];
+                let axis = |row: &[f32; 3]| local[row.iter().position(|&v| v != 0.0).unwrap()];
+                Size::Procedural(axis(&m[0]), axis(&m[1]), axis(&m[2]))
+            }
+            Size::Empty => Size::Empty,
+        }
+    }
+
+    fn dimensions(&self) -> (u32, u32, u32) {
+        match *self {
+            Size::Empty => (0, 0, 0),
+            Size::Procedural(x, y, z) => (x, y, z),
+        }
+    }
+}
+
 #[cfg(feature = "serialize")]
 impl Serialize for Size {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
@@ -568,6 +4134,104 @@ pub enum BrickColor {
     Unique(Color),
 }
 
+impl BrickColor {
+    /// Resolve this color to an actual color, looking up indices in `header2`'s colors.
+    ///
+    /// Returns `None` if the index is out of range.
+    pub fn into_resolved(self, header2: &Header2) -> Option<Color> {
+        match self {
+            BrickColor::Index(index) => header2.colors.get(index as usize).cloned(),
+            BrickColor::Unique(color) => Some(color),
+        }
+    }
+
+    /// Like [`into_resolved`](BrickColor::into_resolved), but borrows the color instead.
+    pub fn as_resolved<'a>(&'a self, header2: &'a Header2) -> Option<&'a Color> {
+        match self {
+            BrickColor::Index(index) => header2.colors.get(*index as usize),
+            BrickColor::Unique(color) => Some(color),
+        }
+    }
+
+    /// Resolve this color to RGBA floats in `[0, 1]`.
+    pub fn to_rgba_f32(&self, header2: &Header2) -> Option<[f32; 4]> {
+        self.as_resolved(header2).map(|c| {
+            [
+                c.r as f32 / 255.0,
+                c.g as f32 / 255.0,
+                c.b as f32 / 255.0,
+                c.a as f32 / 255.0,
+            ]
+        })
+    }
+}
+
+impl fmt::Display for BrickColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BrickColor::Index(index) => write!(f, "Index({})", index),
+            BrickColor::Unique(color) => write!(f, "Unique({})", color.to_hex()),
+        }
+    }
+}
+
+impl FromStr for BrickColor {
+    type Err = ParseBrickColorError;
+
+    /// Parse a color index (`3`), a hex color (`#rrggbbaa`), an array (`[r, g, b, a]`), or
+    /// either form of a formatted `BrickColor` (`Index(3)`, `Unique(#rrggbbaa)`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(inner) = s.strip_prefix("Index(").and_then(|s| s.strip_suffix(')')) {
+            return inner.parse::<BrickColor>().and_then(|color| match color {
+                BrickColor::Index(_) => Ok(color),
+                BrickColor::Unique(_) => Err(ParseBrickColorError::InvalidIndex),
+            });
+        }
+        if let Some(inner) = s.strip_prefix("Unique(").and_then(|s| s.strip_suffix(')')) {
+            return inner.parse::<BrickColor>().and_then(|color| match color {
+                BrickColor::Unique(_) => Ok(color),
+                BrickColor::Index(_) => Err(ParseBrickColorError::InvalidHex),
+            });
+        }
+
+        if let Some(hex) = s.strip_prefix('#') {
+            if hex.len() != 8 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(ParseBrickColorError::InvalidHex);
+            }
+            let v = u32::from_str_radix(hex, 16).map_err(|_| ParseBrickColorError::InvalidHex)?;
+            return Ok(BrickColor::Unique(Color::from_u32_rgba(v)));
+        }
+
+        if let Some(inner) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            let channels = inner
+                .split(',')
+                .map(|c| c.trim().parse::<u8>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| ParseBrickColorError::InvalidArray)?;
+            return match channels[..] {
+                [r, g, b, a] => Ok(BrickColor::Unique(Color { r, g, b, a })),
+                _ => Err(ParseBrickColorError::InvalidArray),
+            };
+        }
+
+        s.parse()
+            .map(BrickColor::Index)
+            .map_err(|_| ParseBrickColorError::InvalidIndex)
+    }
+}
+
+/// An error from parsing a [`BrickColor`] from a string.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseBrickColorError {
+    #[error("invalid color index")]
+    InvalidIndex,
+    #[error("invalid hex color (expected #rrggbbaa)")]
+    InvalidHex,
+    #[error("invalid color array (expected [r, g, b, a])")]
+    InvalidArray,
+}
+
 /// Represents a brick's collision flags.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize), serde(default))]
@@ -594,6 +4258,40 @@ impl Collision {
             tool: state,
         }
     }
+
+    /// Create a `Collision` from the pre-version 10 single collision flag.
+    pub fn from_bits_v9(bit: bool) -> Self {
+        Self::for_all(bit)
+    }
+
+    /// Unpack a `Collision` from the low 4 bits of `bits`, as packed by
+    /// [`to_bits`](Collision::to_bits).
+    pub fn from_bits(bits: u8) -> Self {
+        Collision {
+            player: bits & 1 != 0,
+            weapon: bits & (1 << 1) != 0,
+            interaction: bits & (1 << 2) != 0,
+            tool: bits & (1 << 3) != 0,
+        }
+    }
+
+    /// Pack the flags into a `u8`, as `player | weapon << 1 | interaction << 2 | tool << 3`.
+    pub fn to_bits(&self) -> u8 {
+        self.player as u8
+            | (self.weapon as u8) << 1
+            | (self.interaction as u8) << 2
+            | (self.tool as u8) << 3
+    }
+
+    /// Whether or not every flag is set.
+    pub fn is_full(&self) -> bool {
+        self == &Self::for_all(true)
+    }
+
+    /// Whether or not no flag is set.
+    pub fn is_none(&self) -> bool {
+        self == &Self::for_all(false)
+    }
 }
 
 impl Default for Collision {
@@ -613,7 +4311,7 @@ impl Default for Collision {
 /// * `BCD_ItemSpawn`
 /// * `BCD_Interact`
 /// * `BCD_AudioEmitter`
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Component {
     /// The version of this component.
@@ -637,3 +4335,90 @@ impl Default for Component {
         }
     }
 }
+
+impl Component {
+    /// Replace the component's brick indices.
+    pub fn with_brick_indices(mut self, indices: Vec<u32>) -> Self {
+        self.brick_indices = indices;
+        self
+    }
+
+    /// Add a property named `name` of the Unreal type `ty`.
+    pub fn with_property(mut self, name: impl Into<String>, ty: impl Into<String>) -> Self {
+        self.properties.insert(name.into(), ty.into());
+        self
+    }
+
+    /// Get every brick index that is out of range for a save with `brick_count` bricks.
+    pub fn verify_brick_indices(&self, brick_count: usize) -> Vec<u32> {
+        self.brick_indices
+            .iter()
+            .copied()
+            .filter(|&index| index as usize >= brick_count)
+            .collect()
+    }
+
+    /// Remove every brick index that is out of range for a save with `brick_count` bricks,
+    /// returning how many were removed.
+    pub fn prune_brick_indices(&mut self, brick_count: usize) -> usize {
+        let before = self.brick_indices.len();
+        self.brick_indices
+            .retain(|&index| (index as usize) < brick_count);
+        before - self.brick_indices.len()
+    }
+
+    /// Replace each brick index `i` with `old_to_new[i]`, removing it if that is `None` or
+    /// out of range.
+    pub fn remap_brick_indices(&mut self, old_to_new: &[Option<u32>]) {
+        self.brick_indices = self
+            .brick_indices
+            .iter()
+            .filter_map(|&index| old_to_new.get(index as usize).copied().flatten())
+            .collect();
+    }
+}
+
+/// A builder for a [`Component`]. Duplicate brick indices are removed when built.
+#[derive(Debug, Clone, Default)]
+pub struct ComponentBuilder {
+    component: Component,
+}
+
+impl ComponentBuilder {
+    /// Start building a component with version `version`.
+    pub fn new(version: i32) -> Self {
+        ComponentBuilder {
+            component: Component {
+                version,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Apply the component to the brick at `index`.
+    pub fn with_brick(mut self, index: u32) -> Self {
+        self.component.brick_indices.push(index);
+        self
+    }
+
+    /// Apply the component to the bricks at `indices`.
+    pub fn with_bricks(mut self, indices: &[u32]) -> Self {
+        self.component.brick_indices.extend_from_slice(indices);
+        self
+    }
+
+    /// Add a property named `name` of the Unreal type `ty`.
+    pub fn with_property(mut self, name: &str, ty: &str) -> Self {
+        self.component = self.component.with_property(name, ty);
+        self
+    }
+
+    /// Build the component.
+    pub fn build(mut self) -> Component {
+        let mut seen = HashSet::new();
+        self.component
+            .brick_indices
+            .retain(|&index| seen.insert(index));
+        self.component
+    }
+}
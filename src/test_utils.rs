@@ -0,0 +1,166 @@
+//! Helpers for checking that saves survive being transformed or serialized.
+
+use crate::save::SaveData;
+
+// compare a field of two saves, either returning false or
+// failing with a diff of the field when they differ
+macro_rules! field {
+    ($strict:expr, $name:expr, $a:expr, $b:expr) => {
+        if $a != $b {
+            if $strict {
+                assert_eq!($a, $b, "saves differ at `{}`", $name);
+            }
+            return false;
+        }
+    };
+}
+
+fn compare_saves(a: &SaveData, b: &SaveData, strict: bool) -> bool {
+    field!(strict, "version", a.version, b.version);
+    field!(strict, "game_version", a.game_version, b.game_version);
+
+    let (h1a, h1b) = (&a.header1, &b.header1);
+    field!(strict, "header1.map", h1a.map, h1b.map);
+    field!(
+        strict,
+        "header1.description",
+        h1a.description,
+        h1b.description
+    );
+    field!(
+        strict,
+        "header1.author.name",
+        h1a.author.name,
+        h1b.author.name
+    );
+    field!(strict, "header1.author.id", h1a.author.id, h1b.author.id);
+    field!(
+        strict,
+        "header1.host",
+        h1a.host.as_ref().map(|host| (&host.name, host.id)),
+        h1b.host.as_ref().map(|host| (&host.name, host.id))
+    );
+    field!(strict, "header1.save_time", h1a.save_time, h1b.save_time);
+    field!(
+        strict,
+        "header1.brick_count",
+        h1a.brick_count,
+        h1b.brick_count
+    );
+
+    let (h2a, h2b) = (&a.header2, &b.header2);
+    field!(strict, "header2.mods", h2a.mods, h2b.mods);
+    field!(
+        strict,
+        "header2.brick_assets",
+        h2a.brick_assets,
+        h2b.brick_assets
+    );
+    field!(strict, "header2.colors", h2a.colors, h2b.colors);
+    field!(strict, "header2.materials", h2a.materials, h2b.materials);
+    field!(
+        strict,
+        "header2.brick_owners",
+        h2a.brick_owners
+            .iter()
+            .map(|owner| (&owner.name, owner.id, owner.bricks))
+            .collect::<Vec<_>>(),
+        h2b.brick_owners
+            .iter()
+            .map(|owner| (&owner.name, owner.id, owner.bricks))
+            .collect::<Vec<_>>()
+    );
+    field!(
+        strict,
+        "header2.physical_materials",
+        h2a.physical_materials,
+        h2b.physical_materials
+    );
+    field!(
+        strict,
+        "header2.texture_assets",
+        h2a.texture_assets,
+        h2b.texture_assets
+    );
+
+    field!(strict, "bricks.len()", a.bricks.len(), b.bricks.len());
+    for (i, (ba, bb)) in a.bricks.iter().zip(b.bricks.iter()).enumerate() {
+        let name = |field: &str| format!("bricks[{}].{}", i, field);
+        field!(
+            strict,
+            name("asset_name_index"),
+            ba.asset_name_index,
+            bb.asset_name_index
+        );
+        field!(strict, name("size"), ba.size, bb.size);
+        field!(strict, name("position"), ba.position, bb.position);
+        field!(strict, name("direction"), ba.direction, bb.direction);
+        field!(strict, name("rotation"), ba.rotation, bb.rotation);
+        field!(strict, name("collision"), ba.collision, bb.collision);
+        field!(strict, name("visibility"), ba.visibility, bb.visibility);
+        field!(
+            strict,
+            name("material_index"),
+            ba.material_index,
+            bb.material_index
+        );
+        field!(
+            strict,
+            name("physical_index"),
+            ba.physical_index,
+            bb.physical_index
+        );
+        field!(
+            strict,
+            name("material_intensity"),
+            ba.material_intensity,
+            bb.material_intensity
+        );
+        field!(strict, name("color"), ba.color, bb.color);
+        field!(strict, name("owner_index"), ba.owner_index, bb.owner_index);
+        field!(strict, name("components"), ba.components, bb.components);
+    }
+
+    let mut names = a.components.keys().collect::<Vec<_>>();
+    names.sort_unstable();
+    let mut other_names = b.components.keys().collect::<Vec<_>>();
+    other_names.sort_unstable();
+    field!(strict, "components", names, other_names);
+    for (name, ca) in a.components.iter() {
+        let cb = &b.components[name];
+        let field_name = |field: &str| format!("components[{:?}].{}", name, field);
+        field!(strict, field_name("version"), ca.version, cb.version);
+        field!(
+            strict,
+            field_name("brick_indices"),
+            ca.brick_indices,
+            cb.brick_indices
+        );
+        field!(
+            strict,
+            field_name("properties"),
+            ca.properties,
+            cb.properties
+        );
+    }
+
+    true
+}
+
+/// Whether or not two saves hold the same data. Every field is compared except the preview.
+pub fn saves_equivalent(a: &SaveData, b: &SaveData) -> bool {
+    compare_saves(a, b, false)
+}
+
+/// Assert that two saves hold the same data, like [`saves_equivalent`], panicking with the
+/// first field that differs.
+pub fn assert_saves_equivalent(a: &SaveData, b: &SaveData) {
+    compare_saves(a, b, true);
+}
+
+/// Assert that `data` is unchanged after serializing it to JSON and back.
+pub fn assert_serde_roundtrip(data: &SaveData) {
+    let json = serde_json::to_string(data).expect("failed to serialize save");
+    let back: SaveData = serde_json::from_str(&json).expect("failed to deserialize save");
+    assert_saves_equivalent(data, &back);
+}
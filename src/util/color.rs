@@ -0,0 +1,174 @@
+//! Color palette generation helpers.
+
+use crate::save::{srgb_to_linear, Axis, Brick, BrickColor, Color, Header2};
+
+/// Interpolate between two bytes, where `t` is in `[0, 1]`.
+fn lerp(a: u8, b: u8, t: f32) -> u8 {
+    (a as f32 + (b as f32 - a as f32) * t).round() as u8
+}
+
+/// Convert a linear value in `[0, 1]` to an sRGB byte.
+fn linear_to_srgb(c: f32) -> u8 {
+    let c = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// The interpolation factor for step `i` of `steps`.
+fn step_factor(i: usize, steps: usize) -> f32 {
+    if steps <= 1 {
+        0.0
+    } else {
+        i as f32 / (steps - 1) as f32
+    }
+}
+
+/// Generate `steps` colors linearly interpolating from `start` to `end` in sRGB space.
+pub fn generate_gradient(start: Color, end: Color, steps: usize) -> Vec<Color> {
+    (0..steps)
+        .map(|i| {
+            let t = step_factor(i, steps);
+            Color {
+                r: lerp(start.r, end.r, t),
+                g: lerp(start.g, end.g, t),
+                b: lerp(start.b, end.b, t),
+                a: lerp(start.a, end.a, t),
+            }
+        })
+        .collect()
+}
+
+/// Generate `steps` colors interpolating from `start` to `end` in linear (gamma-decoded) space.
+///
+/// This produces gradients that look more even than [`generate_gradient`].
+pub fn generate_gradient_linear(start: Color, end: Color, steps: usize) -> Vec<Color> {
+    let channel = |a: u8, b: u8, t: f32| {
+        let (a, b) = (srgb_to_linear(a), srgb_to_linear(b));
+        linear_to_srgb(a + (b - a) * t)
+    };
+
+    (0..steps)
+        .map(|i| {
+            let t = step_factor(i, steps);
+            Color {
+                r: channel(start.r, end.r, t),
+                g: channel(start.g, end.g, t),
+                b: channel(start.b, end.b, t),
+                a: lerp(start.a, end.a, t),
+            }
+        })
+        .collect()
+}
+
+/// Generate `steps` colors evenly spaced around the HSV hue wheel, at full saturation and value.
+pub fn generate_palette_rainbow(steps: usize) -> Vec<Color> {
+    (0..steps)
+        .map(|i| {
+            let hue = i as f32 / steps as f32 * 6.0;
+            let x = 1.0 - (hue % 2.0 - 1.0).abs();
+            let (r, g, b) = match hue as u32 {
+                0 => (1.0, x, 0.0),
+                1 => (x, 1.0, 0.0),
+                2 => (0.0, 1.0, x),
+                3 => (0.0, x, 1.0),
+                4 => (x, 0.0, 1.0),
+                _ => (1.0, 0.0, x),
+            };
+            Color {
+                r: (r * 255.0f32).round() as u8,
+                g: (g * 255.0f32).round() as u8,
+                b: (b * 255.0f32).round() as u8,
+                a: 255,
+            }
+        })
+        .collect()
+}
+
+/// Color `bricks` with `gradient` according to their position along `axis`.
+///
+/// The brick furthest in the negative direction gets the first color, and the brick furthest
+/// in the positive direction gets the last. The gradient's colors are added to `header2`'s
+/// palette (reusing existing entries) and bricks are set to refer to them.
+pub fn apply_gradient_to_bricks(
+    bricks: &mut [Brick],
+    header2: &mut Header2,
+    gradient: Vec<Color>,
+    axis: Axis,
+) {
+    if gradient.is_empty() || bricks.is_empty() {
+        return;
+    }
+
+    let indices = gradient
+        .into_iter()
+        .map(
+            |color| match header2.colors.iter().position(|c| *c == color) {
+                Some(i) => i as u32,
+                None => {
+                    header2.colors.push(color);
+                    header2.colors.len() as u32 - 1
+                }
+            },
+        )
+        .collect::<Vec<_>>();
+
+    let coords = bricks.iter().map(|b| axis.coordinate(b.position));
+    let min = coords.clone().min().unwrap();
+    let max = coords.max().unwrap();
+    let span = (max as i64 - min as i64) as f64;
+
+    for brick in bricks.iter_mut() {
+        let t = if span == 0.0 {
+            0.0
+        } else {
+            (axis.coordinate(brick.position) as i64 - min as i64) as f64 / span
+        };
+        let i = (t * (indices.len() - 1) as f64).round() as usize;
+        brick.color = BrickColor::Index(indices[i]);
+    }
+}
+
+/// Sort `colors` by [`Color::hue`], returning the new index of each old index.
+///
+/// Pass the result to [`apply_palette_mapping`] to update bricks using the palette.
+pub fn sort_palette_by_hue(colors: &mut [Color]) -> Vec<u32> {
+    sort_palette_by_key(colors, Color::hue)
+}
+
+/// Sort `colors` by [`Color::luminance`], returning the new index of each old index.
+///
+/// Pass the result to [`apply_palette_mapping`] to update bricks using the palette.
+pub fn sort_palette_by_luminance(colors: &mut [Color]) -> Vec<u32> {
+    sort_palette_by_key(colors, Color::luminance)
+}
+
+/// Stable sort `colors` by `key`, returning the new index of each old index.
+fn sort_palette_by_key(colors: &mut [Color], key: impl Fn(&Color) -> f32) -> Vec<u32> {
+    // order[new] = old
+    let mut order = (0..colors.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| key(&colors[a]).total_cmp(&key(&colors[b])));
+
+    let sorted = order.iter().map(|&i| colors[i].clone()).collect::<Vec<_>>();
+    colors.clone_from_slice(&sorted);
+
+    let mut mapping = vec![0u32; order.len()];
+    for (new, &old) in order.iter().enumerate() {
+        mapping[old] = new as u32;
+    }
+    mapping
+}
+
+/// Update the color index of every brick in `bricks` using `mapping`, which gives the new
+/// index of each old index. Indices outside of `mapping` are left alone.
+pub fn apply_palette_mapping(bricks: &mut [Brick], mapping: &[u32]) {
+    for brick in bricks.iter_mut() {
+        if let BrickColor::Index(index) = &mut brick.color {
+            if let Some(&new) = mapping.get(*index as usize) {
+                *index = new;
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Palette quantization of unique brick colors.
+
+use crate::save::{BrickColor, Color, SaveData};
+
+/// Reduce `colors` to `target_palette_size` representative colors using the median-cut
+/// algorithm.
+///
+/// This returns fewer than `target_palette_size` colors when `colors` has fewer distinct
+/// colors than that, and none when `colors` is empty. Two buckets can average to the same
+/// color, so the palette may also hold duplicates.
+pub fn median_cut_quantize(colors: &[Color], target_palette_size: usize) -> Vec<Color> {
+    if colors.is_empty() || target_palette_size == 0 {
+        return vec![];
+    }
+
+    let mut buckets = vec![colors.to_vec()];
+    while buckets.len() < target_palette_size {
+        // split the bucket with the widest range on any channel
+        let (index, channel, range) = buckets
+            .iter()
+            .enumerate()
+            .map(|(i, bucket)| {
+                let (channel, range) = widest_channel(bucket);
+                (i, channel, range)
+            })
+            .max_by_key(|&(i, _, range)| (range, std::cmp::Reverse(i)))
+            .unwrap();
+        if range == 0 {
+            break;
+        }
+
+        let mut bucket = buckets.swap_remove(index);
+        bucket.sort_unstable_by_key(|color| channels(color)[channel]);
+        let upper = bucket.split_off(bucket.len() / 2);
+        buckets.push(bucket);
+        buckets.push(upper);
+    }
+
+    buckets.iter().map(|bucket| average(bucket)).collect()
+}
+
+/// Replace every [`BrickColor::Unique`] color in `data` with an index into a palette of at
+/// most `target_palette_size` colors made with [`median_cut_quantize`].
+///
+/// The palette is added to `header2`, then duplicate colors are removed with
+/// [`SaveData::normalize_colors`], so `header2` can gain fewer than `target_palette_size`
+/// colors, including when palette colors match ones already in `header2`.
+pub fn quantize_save_colors(data: &mut SaveData, target_palette_size: usize) {
+    let unique = data
+        .bricks
+        .iter()
+        .filter_map(|brick| match &brick.color {
+            BrickColor::Unique(color) => Some(color.clone()),
+            BrickColor::Index(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let palette = median_cut_quantize(&unique, target_palette_size);
+    if palette.is_empty() {
+        return;
+    }
+
+    let base = data.header2.colors.len() as u32;
+    for brick in data.bricks.iter_mut() {
+        if let BrickColor::Unique(color) = &brick.color {
+            let (index, _) = color.nearest_in_palette(&palette).unwrap();
+            brick.color = BrickColor::Index(base + index as u32);
+        }
+    }
+    data.header2.colors.extend(palette);
+    data.normalize_colors();
+}
+
+fn channels(color: &Color) -> [u8; 4] {
+    [color.r, color.g, color.b, color.a]
+}
+
+/// Get the channel with the widest range of values in `bucket`, and its range.
+fn widest_channel(bucket: &[Color]) -> (usize, u8) {
+    let mut min = [u8::MAX; 4];
+    let mut max = [0u8; 4];
+    for color in bucket {
+        for (channel, value) in channels(color).into_iter().enumerate() {
+            min[channel] = min[channel].min(value);
+            max[channel] = max[channel].max(value);
+        }
+    }
+    (0..4)
+        .map(|channel| (channel, max[channel] - min[channel]))
+        .max_by_key(|&(channel, range)| (range, std::cmp::Reverse(channel)))
+        .unwrap()
+}
+
+/// Get the average of the colors in `bucket`, which must not be empty.
+fn average(bucket: &[Color]) -> Color {
+    let mut sums = [0u64; 4];
+    for color in bucket {
+        for (sum, value) in sums.iter_mut().zip(channels(color)) {
+            *sum += value as u64;
+        }
+    }
+    let len = bucket.len() as u64;
+    let [r, g, b, a] = sums.map(|sum| ((sum + len / 2) / len) as u8);
+    Color { r, g, b, a }
+}
@@ -0,0 +1,154 @@
+//! Helpers for editing save files in place.
+
+use std::fs::OpenOptions;
+#[cfg(not(feature = "atomic-write"))]
+use std::fs::{self, File};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::Path;
+#[cfg(not(feature = "atomic-write"))]
+use std::path::PathBuf;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use thiserror::Error;
+
+use crate::read::{ReadError, SaveReader};
+use crate::save::{DateTime, Header1, SaveData, User, Utc};
+use crate::write::{header1_bytes, SaveWriter, WriteError};
+use crate::SAVE_VERSION;
+
+/// Changes to make to the first header of a save. Fields left as `None` are unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct Header1Patch {
+    pub map: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<User>,
+    pub host: Option<User>,
+    pub save_time: Option<DateTime<Utc>>,
+}
+
+impl Header1Patch {
+    /// Apply the changes to `header1`.
+    pub fn apply(self, header1: &mut Header1) {
+        if let Some(map) = self.map {
+            header1.map = map;
+        }
+        if let Some(description) = self.description {
+            header1.description = description;
+        }
+        if let Some(author) = self.author {
+            header1.author = author;
+        }
+        if let Some(host) = self.host {
+            header1.host = Some(host);
+        }
+        if let Some(save_time) = self.save_time {
+            header1.save_time = Some(save_time);
+        }
+    }
+}
+
+/// An error from patching a save file.
+#[derive(Error, Debug)]
+pub enum PatchError {
+    #[error("generic io error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("read error: {0}")]
+    ReadError(#[from] ReadError),
+    #[error("write error: {0}")]
+    WriteError(#[from] WriteError),
+}
+
+/// Apply `patch` to the first header of the save file at `path`.
+///
+/// When the save is of the current version and the patched header fits in the space taken
+/// by the old one, only the header is rewritten, padded with zeros to the old length.
+/// Otherwise, the whole save is read and written to a temporary file that then replaces it.
+///
+/// A padded header is not what [`SaveWriter`] would write: the zeros either follow the
+/// uncompressed header data, counted in its uncompressed size, or follow the zlib stream,
+/// counted in its compressed size. [`SaveReader`] ignores them in both cases, but this has
+/// not been checked against the game's own reader.
+pub fn patch_header1(path: impl AsRef<Path>, patch: Header1Patch) -> Result<(), PatchError> {
+    let path = path.as_ref();
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+
+    let mut reader = SaveReader::new(&mut file)?;
+    let version = reader.version;
+    let start = reader.get_mut().stream_position()?;
+    let mut header1 = reader.read_header1()?;
+    let end = reader.get_mut().stream_position()?;
+    patch.apply(&mut header1);
+
+    if version == SAVE_VERSION {
+        let bytes = header1_bytes(&header1, header1.brick_count as usize)?;
+        if let Some(section) = padded_section(bytes, (end - start) as usize)? {
+            file.seek(SeekFrom::Start(start))?;
+            file.write_all(&section)?;
+            return Ok(());
+        }
+    }
+
+    file.seek(SeekFrom::Start(0))?;
+    let mut data = SaveReader::new(&mut file)?.read_all()?;
+    data.header1 = header1;
+    drop(file);
+    replace_save(path, data)
+}
+
+/// Write `data` to a temporary file next to `path`, then rename it over `path`.
+#[cfg(feature = "atomic-write")]
+fn replace_save(path: &Path, data: SaveData) -> Result<(), PatchError> {
+    Ok(SaveWriter::write_atomic(path, data)?)
+}
+
+/// Write `data` to a temporary file next to `path`, then rename it over `path`.
+#[cfg(not(feature = "atomic-write"))]
+fn replace_save(path: &Path, data: SaveData) -> Result<(), PatchError> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+
+    let result = File::create(&temp)
+        .map_err(PatchError::from)
+        .and_then(|mut file| {
+            SaveWriter::new(&mut file, data).write()?;
+            file.sync_all()?;
+            Ok(())
+        })
+        .and_then(|()| Ok(fs::rename(&temp, path)?));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Build a compressed section holding `bytes` that is exactly `len` bytes long, or `None` if
+/// `bytes` can't fit.
+fn padded_section(bytes: Vec<u8>, len: usize) -> io::Result<Option<Vec<u8>>> {
+    let data_len = match len.checked_sub(8) {
+        Some(data_len) => data_len,
+        None => return Ok(None),
+    };
+
+    let (uncompressed_size, compressed_size, mut data) = if bytes.len() <= data_len {
+        // uncompressed, with the padding counted as part of the data
+        (data_len, 0, bytes)
+    } else {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(&bytes)?;
+        let compressed = encoder.finish()?;
+        if compressed.len() > data_len {
+            return Ok(None);
+        }
+        // compressed, with the padding following the zlib stream
+        (bytes.len(), data_len, compressed)
+    };
+    data.resize(data_len, 0);
+
+    let mut section = Vec::with_capacity(len);
+    section.extend_from_slice(&(uncompressed_size as i32).to_le_bytes());
+    section.extend_from_slice(&(compressed_size as i32).to_le_bytes());
+    section.extend(data);
+    Ok(Some(section))
+}
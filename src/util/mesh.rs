@@ -0,0 +1,78 @@
+//! Triangle meshes of brick bounding boxes.
+
+use std::collections::HashMap;
+
+use crate::save::{Brick, Header2, SaveData};
+
+/// The number of save units in one world unit.
+const UNITS_PER_WORLD_UNIT: f32 = 10.0;
+
+/// The distance below which vertices are merged by [`save_to_indexed_mesh`].
+const VERTEX_EPSILON: f32 = 0.001;
+
+/// Build the 12 triangles of the box around `brick`, in world units, as 36 vertices.
+///
+/// The box is the brick's axis-aligned bounds after its direction and rotation are applied.
+/// Triangles are wound counter-clockwise when seen from outside the box. Bricks with no
+/// volume produce no triangles.
+pub fn brick_to_triangles(brick: &Brick, header2: &Header2) -> Vec<[f32; 3]> {
+    let assets = &header2.brick_assets;
+    let half = [0, 1, 2].map(|axis| super::get_axis_size(brick, assets, axis) as f32);
+    if half.contains(&0.0) {
+        return vec![];
+    }
+
+    let (x, y, z) = brick.position;
+    let center = [x as f32, y as f32, z as f32];
+    let corner = |signs: [f32; 3]| {
+        [0, 1, 2].map(|axis| (center[axis] + signs[axis] * half[axis]) / UNITS_PER_WORLD_UNIT)
+    };
+
+    let mut vertices = Vec::with_capacity(36);
+    for axis in 0..3 {
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        for side in [-1.0, 1.0] {
+            // a quad on this side of the box, counter-clockwise around `axis * side`
+            let mut quad = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+            if side < 0.0 {
+                quad.reverse();
+            }
+            let quad = quad.map(|(su, sv)| {
+                let mut signs = [0.0; 3];
+                signs[axis] = side;
+                signs[u] = su;
+                signs[v] = sv;
+                corner(signs)
+            });
+            vertices.extend([quad[0], quad[1], quad[2], quad[0], quad[2], quad[3]]);
+        }
+    }
+    vertices
+}
+
+/// Build the triangles of every brick in `data` with [`brick_to_triangles`], one after another.
+pub fn save_to_triangle_soup(data: &SaveData) -> Vec<[f32; 3]> {
+    data.bricks
+        .iter()
+        .flat_map(|brick| brick_to_triangles(brick, &data.header2))
+        .collect()
+}
+
+/// Build the triangles of every brick in `data` as a vertex list and a list of indices into
+/// it, three per triangle.
+///
+/// Vertices that round to the same multiple of 0.001 world units are shared.
+pub fn save_to_indexed_mesh(data: &SaveData) -> (Vec<[f32; 3]>, Vec<u32>) {
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    let mut lookup = HashMap::new();
+    for vertex in save_to_triangle_soup(data) {
+        let key = vertex.map(|v| (v / VERTEX_EPSILON).round() as i64);
+        let index = *lookup.entry(key).or_insert_with(|| {
+            vertices.push(vertex);
+            vertices.len() as u32 - 1
+        });
+        indices.push(index);
+    }
+    (vertices, indices)
+}
@@ -1,6 +1,11 @@
 //! Utility methods and types for dealing with save files.
 
+pub mod color;
+pub mod color_quantize;
+pub mod io;
+pub mod mesh;
 pub mod octree;
+pub mod transform;
 
 use std::collections::HashMap;
 
@@ -239,6 +244,8 @@ pub fn get_axis_size(brick: &Brick, assets: &[String], axis: u8) -> u32 {
 
 /// Rotation helper methods.
 pub mod rotation {
+    use crate::save::{Direction, Rotation};
+
     /// Convert a direction and a rotation to its orientation number.
     ///
     /// Equivalent to `(direction << 2) | rotation`.
@@ -253,6 +260,22 @@ pub mod rotation {
 
     /// Rotate orientation A (`ad` and `ar`) by orientation B (`bd` and `br`).
     pub fn rotate_direction((ad, ar): (u8, u8), (bd, br): (u8, u8)) -> (u8, u8) {
-        o2d(super::ROTATION_TABLE[(d2o(ad, ar) * 24 + d2o(bd, br)) as usize])
+        o2d(super::ROTATION_TABLE[d2o(ad, ar) as usize * 24 + d2o(bd, br) as usize])
+    }
+
+    /// Compose the orientation `dir` and `rot` with the orientation `other_dir` and `other_rot`.
+    ///
+    /// This is the typed equivalent of [`rotate_direction`].
+    pub fn orientation_compose(
+        dir: Direction,
+        rot: Rotation,
+        other_dir: Direction,
+        other_rot: Rotation,
+    ) -> (Direction, Rotation) {
+        let (d, r) = rotate_direction((dir as u8, rot as u8), (other_dir as u8, other_rot as u8));
+        (
+            Direction::try_from(d).unwrap(),
+            Rotation::try_from(r).unwrap(),
+        )
     }
 }
@@ -4,11 +4,11 @@
 //! not other exposed items from this module.
 
 use std::cmp;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 use std::hash::Hash;
 
-use crate::save::{Brick, Direction, SaveData};
+use crate::save::{Axis, Brick, Direction, SaveData};
 
 use super::get_axis_size;
 
@@ -18,6 +18,23 @@ pub const RIGHT: i32 = 1;
 pub const BACK: i32 = 2;
 pub const BOTTOM: i32 = 4;
 
+/// The minimum and maximum corners of a brick.
+type BrickBounds = ((i32, i32, i32), (i32, i32, i32));
+
+/// Check if brick bounds have a nonzero size along every axis.
+fn has_volume(min: (i32, i32, i32), max: (i32, i32, i32)) -> bool {
+    min.0 < max.0 && min.1 < max.1 && min.2 < max.2
+}
+
+const DIRECTIONS: [Direction; 6] = [
+    Direction::XPositive,
+    Direction::XNegative,
+    Direction::YPositive,
+    Direction::YNegative,
+    Direction::ZPositive,
+    Direction::ZNegative,
+];
+
 /// An integer point in space.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Point {
@@ -34,15 +51,11 @@ impl Point {
 
     /// Get a chunk from a point.
     pub fn chunk(&self) -> Self {
-        fn div_floor(a: i32) -> i32 {
-            if a >= 0 {
-                a / CHUNK_SIZE
-            } else {
-                (a - CHUNK_SIZE - 1) / CHUNK_SIZE
-            }
-        }
-
-        Self::new(div_floor(self.x), div_floor(self.y), div_floor(self.z))
+        Self::new(
+            self.x.div_euclid(CHUNK_SIZE),
+            self.y.div_euclid(CHUNK_SIZE),
+            self.z.div_euclid(CHUNK_SIZE),
+        )
     }
 
     /// Check if a point is contained by the bounds formed by `min` and `max`.
@@ -263,27 +276,83 @@ impl<T: PartialEq + Eq + Hash + Copy> Node<T> {
             NodeValue::Children(nodes) => nodes[self.point.octant(point) as usize].get(point),
         }
     }
+
+    /// Count the leaves in this subtree that hold a value.
+    pub fn count(&self) -> usize {
+        match &self.value {
+            NodeValue::Value(Some(_)) => 1,
+            NodeValue::Value(None) => 0,
+            NodeValue::Children(nodes) => nodes.iter().map(Node::count).sum(),
+        }
+    }
 }
 
 /// A series of chunks.
 #[derive(Default)]
 pub struct ChunkTree<T: PartialEq + Eq + Hash + Copy> {
-    pub chunks: Vec<(Node<T>, Point)>,
+    /// The chunks, keyed by their `(x, y, z)` position in chunks.
+    pub chunks: HashMap<(i32, i32, i32), Node<T>>,
 }
 
 impl<T: PartialEq + Eq + Hash + Copy> ChunkTree<T> {
     /// Instantiate an empty chunk tree.
     pub fn new() -> Self {
-        ChunkTree { chunks: vec![] }
+        ChunkTree {
+            chunks: HashMap::new(),
+        }
     }
 
     /// Reduce all child trees.
     pub fn reduce(&mut self) {
-        for (node, _) in self.chunks.iter_mut() {
+        for node in self.chunks.values_mut() {
             node.reduce();
         }
     }
 
+    /// Count the leaves in every chunk that hold a value.
+    pub fn count(&self) -> usize {
+        self.chunks.values().map(|node| node.count()).sum()
+    }
+
+    /// Iterate over every occupied leaf in every chunk, yielding the leaf's center point
+    /// and its value.
+    ///
+    /// A value that fills several leaves is yielded once for each of them.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> + '_ {
+        let mut stack = self.chunks.values().collect::<Vec<_>>();
+        std::iter::from_fn(move || {
+            while let Some(node) = stack.pop() {
+                match &node.value {
+                    NodeValue::Value(Some(value)) => return Some((node.point, value)),
+                    NodeValue::Value(None) => (),
+                    NodeValue::Children(children) => stack.extend(children.iter().rev()),
+                }
+            }
+            None
+        })
+    }
+
+    /// Iterate over the value of every occupied leaf. See [`iter`](ChunkTree::iter).
+    pub fn values(&self) -> impl Iterator<Item = &T> + '_ {
+        self.iter().map(|(_, value)| value)
+    }
+
+    /// Consume the tree, iterating over the value of every occupied leaf.
+    /// See [`iter`](ChunkTree::iter).
+    pub fn into_values(self) -> impl Iterator<Item = T> {
+        let mut stack = self.chunks.into_values().collect::<Vec<_>>();
+        std::iter::from_fn(move || {
+            while let Some(node) = stack.pop() {
+                match node.value {
+                    NodeValue::Value(Some(value)) => return Some(value),
+                    NodeValue::Value(None) => (),
+                    NodeValue::Children(children) => stack.extend(children.into_iter().rev()),
+                }
+            }
+            None
+        })
+    }
+
     /// Get a list of chunks contained by a `min` and a `max`.
     pub fn chunks_from_bounds(&self, min: Point, max: Point) -> Vec<(Point, Point)> {
         let min_chunk = min.chunk();
@@ -343,19 +412,14 @@ impl<T: PartialEq + Eq + Hash + Copy> ChunkTree<T> {
     /// Get a reference to chunk at `point`, if it exists.
     pub fn chunk_at(&self, point: Point) -> Option<&Node<T>> {
         let chunk_pos = point.chunk();
-        self.chunks
-            .iter()
-            .find(|(_, p)| *p == chunk_pos)
-            .map(|(node, _)| node)
+        self.chunks.get(&(chunk_pos.x, chunk_pos.y, chunk_pos.z))
     }
 
     /// Get a mutable reference to the chunk at `point`, if it exists.
     pub fn chunk_at_mut(&mut self, point: Point) -> Option<&mut Node<T>> {
         let chunk_pos = point.chunk();
         self.chunks
-            .iter_mut()
-            .find(|(_, p)| *p == chunk_pos)
-            .map(|(node, _)| node)
+            .get_mut(&(chunk_pos.x, chunk_pos.y, chunk_pos.z))
     }
 
     /// Search for `T`s contained within `min_bound` and `max_bound`.
@@ -377,15 +441,11 @@ impl<T: PartialEq + Eq + Hash + Copy> ChunkTree<T> {
     /// Insert a `T` into the chunks, from `min_bound` to `max_bound`.
     pub fn insert(&mut self, value: T, min_bound: Point, max_bound: Point) {
         for (min, max) in self.chunks_from_bounds(min_bound, max_bound).into_iter() {
-            match self.chunk_at_mut(min) {
-                Some(node) => node.insert(value, min, max),
-                None => {
-                    let chunk_pos = min.chunk();
-                    let mut chunk = Node::new(chunk_pos.chunk_midpoint(), 10, None);
-                    chunk.insert(value, min, max);
-                    self.chunks.push((chunk, chunk_pos));
-                }
-            }
+            let chunk_pos = min.chunk();
+            self.chunks
+                .entry((chunk_pos.x, chunk_pos.y, chunk_pos.z))
+                .or_insert_with(|| Node::new(chunk_pos.chunk_midpoint(), 10, None))
+                .insert(value, min, max);
         }
     }
 }
@@ -396,6 +456,8 @@ pub struct SaveOctree {
     data: SaveData,
     /// The chunks in the octree.
     tree: ChunkTree<usize>,
+    /// Whether or not the save may have changed since the octree was built.
+    stale: bool,
 }
 
 impl SaveOctree {
@@ -404,26 +466,90 @@ impl SaveOctree {
         let mut tree = SaveOctree {
             data,
             tree: ChunkTree::new(),
+            stale: false,
         };
-        for (i, brick) in tree.data.bricks.iter().enumerate() {
-            let (min, max) = tree.brick_bounds(brick);
+        tree.rebuild();
+        tree
+    }
+
+    /// Rebuild the octree from the bricks in the save, e.g. after changing them through
+    /// [`data_mut`](SaveOctree::data_mut).
+    pub fn rebuild(&mut self) {
+        self.stale = false;
+        self.tree = ChunkTree::new();
+        for (i, brick) in self.data.bricks.iter().enumerate() {
+            let (min, max) = self.brick_bounds(brick);
             if min != max {
-                tree.tree.insert(i, min.into(), max.into());
+                self.tree.insert(i, min.into(), max.into());
             }
         }
-        tree
+    }
+
+    /// Split a save in two along `axis`: the first save holds the bricks whose position on
+    /// `axis` is less than `value`, and the second holds the rest.
+    ///
+    /// Both saves keep the original `header2`, with brick owner counts and component
+    /// brick indices updated to match their bricks.
+    pub fn split_at(data: SaveData, axis: Axis, value: i32) -> (SaveData, SaveData) {
+        let mut below = data.clone();
+        let mut above = data;
+        below.filter_bricks(|brick| axis.coordinate(brick.position) < value);
+        above.filter_bricks(|brick| axis.coordinate(brick.position) >= value);
+        (below, above)
     }
 
     /// Take a reference to the inner `SaveData`.
     ///
-    /// This cannot be mutable as the octree would have to rebuild.
-    /// If you need to alter the SaveData and traverse again, instead use
-    /// `into_inner()` to take out the `SaveData`, make your changes,
-    /// and reconstruct with `new(SaveData)`.
+    /// If you need to alter the SaveData and traverse again, use
+    /// [`data_mut`](SaveOctree::data_mut) followed by [`rebuild`](SaveOctree::rebuild).
     pub fn data(&self) -> &SaveData {
         &self.data
     }
 
+    /// Take a mutable reference to the inner `SaveData`, marking the octree as stale.
+    ///
+    /// Until [`ensure_built`](SaveOctree::ensure_built) or [`rebuild`](SaveOctree::rebuild)
+    /// is called, every query that searches the octree panics: [`bricks_in`],
+    /// [`bricks_at_z`], [`bricks_in_z_range`], [`bricks_in_cylinder`], [`bricks_in_circle`],
+    /// [`brick_at_exact`], [`bounds_side`], [`brick_side`], [`interior_bricks`],
+    /// [`bricks_touching`], [`adjacency_graph`], [`adjacency_graph_directed`], and
+    /// [`iter_chunks`].
+    ///
+    /// [`bricks_in`]: SaveOctree::bricks_in
+    /// [`bricks_at_z`]: SaveOctree::bricks_at_z
+    /// [`bricks_in_z_range`]: SaveOctree::bricks_in_z_range
+    /// [`bricks_in_cylinder`]: SaveOctree::bricks_in_cylinder
+    /// [`bricks_in_circle`]: SaveOctree::bricks_in_circle
+    /// [`brick_at_exact`]: SaveOctree::brick_at_exact
+    /// [`bounds_side`]: SaveOctree::bounds_side
+    /// [`brick_side`]: SaveOctree::brick_side
+    /// [`interior_bricks`]: SaveOctree::interior_bricks
+    /// [`bricks_touching`]: SaveOctree::bricks_touching
+    /// [`adjacency_graph`]: SaveOctree::adjacency_graph
+    /// [`adjacency_graph_directed`]: SaveOctree::adjacency_graph_directed
+    /// [`iter_chunks`]: SaveOctree::iter_chunks
+    pub fn data_mut(&mut self) -> &mut SaveData {
+        self.stale = true;
+        &mut self.data
+    }
+
+    /// Rebuild the octree if the save may have changed through
+    /// [`data_mut`](SaveOctree::data_mut) since it was last built.
+    pub fn ensure_built(&mut self) {
+        if self.stale {
+            self.rebuild();
+        }
+    }
+
+    /// The chunks in the octree, checking that they match the save.
+    fn tree(&self) -> &ChunkTree<usize> {
+        assert!(
+            !self.stale,
+            "SaveOctree queried after data_mut without calling ensure_built or rebuild"
+        );
+        &self.tree
+    }
+
     /// Get the size of a brick. This is its absolute size, regardless of rotation.
     pub fn brick_size(&self, brick: &Brick) -> (u32, u32, u32) {
         (
@@ -453,13 +579,128 @@ impl SaveOctree {
     /// Fetch all bricks within some volume in space. This includes bricks that are partially
     /// in this volume.
     pub fn bricks_in(&self, min: (i32, i32, i32), max: (i32, i32, i32)) -> Vec<&Brick> {
-        self.tree
+        self.tree()
             .search(min.into(), max.into())
             .into_iter()
             .map(|idx| &self.data.bricks[idx])
             .collect()
     }
 
+    /// Fetch all bricks positioned at exactly `z` on the Z axis.
+    pub fn bricks_at_z(&self, z: i32) -> Vec<&Brick> {
+        self.bricks_in_z_range(z, z + 1)
+    }
+
+    /// Fetch all bricks positioned between `min_z` (inclusive) and `max_z` (exclusive) on the Z axis.
+    pub fn bricks_in_z_range(&self, min_z: i32, max_z: i32) -> Vec<&Brick> {
+        if min_z >= max_z {
+            return vec![];
+        }
+
+        // only search the chunks that overlap the Z range
+        let mut found = HashSet::new();
+        for &(x, y, z) in self.tree().chunks.keys() {
+            let (chunk_min_z, chunk_max_z) = (z * CHUNK_SIZE, (z + 1) * CHUNK_SIZE);
+            if chunk_max_z <= min_z || chunk_min_z >= max_z {
+                continue;
+            }
+
+            found.extend(self.tree().search(
+                Point::new(x * CHUNK_SIZE, y * CHUNK_SIZE, cmp::max(min_z, chunk_min_z)),
+                Point::new(
+                    (x + 1) * CHUNK_SIZE,
+                    (y + 1) * CHUNK_SIZE,
+                    cmp::min(max_z, chunk_max_z),
+                ),
+            ));
+        }
+
+        let mut bricks = found
+            .into_iter()
+            .filter(|&idx| (min_z..max_z).contains(&self.data.bricks[idx].position.2))
+            .collect::<Vec<_>>();
+        bricks.sort_unstable();
+        bricks
+            .into_iter()
+            .map(|idx| &self.data.bricks[idx])
+            .collect()
+    }
+
+    /// Fetch all bricks positioned within `radius` of `(center_x, center_y)` on the XY plane
+    /// and between `min_z` and `max_z` (both inclusive) on the Z axis.
+    pub fn bricks_in_cylinder(
+        &self,
+        center_x: i32,
+        center_y: i32,
+        min_z: i32,
+        max_z: i32,
+        radius: i32,
+    ) -> Vec<&Brick> {
+        if radius < 0 || min_z > max_z {
+            return vec![];
+        }
+
+        let radius_sq = radius as i64 * radius as i64;
+        let mut bricks = self
+            .tree()
+            .search(
+                Point::new(center_x - radius, center_y - radius, min_z),
+                Point::new(center_x + radius + 1, center_y + radius + 1, max_z + 1),
+            )
+            .into_iter()
+            .filter(|&idx| {
+                let (x, y, z) = self.data.bricks[idx].position;
+                let (dx, dy) = (x as i64 - center_x as i64, y as i64 - center_y as i64);
+                (min_z..=max_z).contains(&z) && dx * dx + dy * dy <= radius_sq
+            })
+            .collect::<Vec<_>>();
+        bricks.sort_unstable();
+        bricks
+            .into_iter()
+            .map(|idx| &self.data.bricks[idx])
+            .collect()
+    }
+
+    /// Fetch all bricks positioned within `radius` of `(center_x, center_y)` on the XY plane,
+    /// at any height.
+    pub fn bricks_in_circle(&self, center_x: i32, center_y: i32, radius: i32) -> Vec<&Brick> {
+        match (self.min_z(), self.max_z()) {
+            (Some(min_z), Some(max_z)) => {
+                self.bricks_in_cylinder(center_x, center_y, min_z, max_z, radius)
+            }
+            _ => vec![],
+        }
+    }
+
+    /// The Z position of the highest brick in the save, if there are any bricks.
+    pub fn max_z(&self) -> Option<i32> {
+        self.data.bricks.iter().map(|brick| brick.position.2).max()
+    }
+
+    /// The Z position of the lowest brick in the save, if there are any bricks.
+    pub fn min_z(&self) -> Option<i32> {
+        self.data.bricks.iter().map(|brick| brick.position.2).min()
+    }
+
+    /// Fetch all bricks positioned at exactly `pos`.
+    ///
+    /// The octree only stores one brick per cell of space, so of several bricks that occupy
+    /// exactly the same space, only the last one in the save is found. Use
+    /// [`SaveData::build_position_index`] to find all of them.
+    pub fn brick_at_exact(&self, pos: (i32, i32, i32)) -> Vec<&Brick> {
+        let mut bricks = self
+            .tree()
+            .search(pos.into(), Point::from(pos).shifted(1, 1, 1))
+            .into_iter()
+            .filter(|&idx| self.data.bricks[idx].position == pos)
+            .collect::<Vec<_>>();
+        bricks.sort_unstable();
+        bricks
+            .into_iter()
+            .map(|idx| &self.data.bricks[idx])
+            .collect()
+    }
+
     /// Fetch all bricks that bound a volume on one of its sides. This includes bricks that are partially
     /// in this volume.
     pub fn bounds_side(
@@ -468,37 +709,45 @@ impl SaveOctree {
         max: (i32, i32, i32),
         dir: Direction,
     ) -> Vec<&Brick> {
-        let indices = match dir {
-            Direction::XPositive => self.tree.search(
+        self.side_indices(min, max, dir)
+            .into_iter()
+            .map(|idx| &self.data.bricks[idx])
+            .collect()
+    }
+
+    /// Get the indices of all bricks that bound a volume on one of its sides.
+    fn side_indices(
+        &self,
+        min: (i32, i32, i32),
+        max: (i32, i32, i32),
+        dir: Direction,
+    ) -> HashSet<usize> {
+        match dir {
+            Direction::XPositive => self.tree().search(
                 Point::new(max.0, min.1, min.2),
                 Point::new(max.0 + 1, max.1, max.2),
             ),
-            Direction::XNegative => self.tree.search(
+            Direction::XNegative => self.tree().search(
                 Point::new(min.0 - 1, min.1, min.2),
                 Point::new(min.0, max.1, max.2),
             ),
-            Direction::YPositive => self.tree.search(
+            Direction::YPositive => self.tree().search(
                 Point::new(min.0, max.1, min.2),
                 Point::new(max.0, max.1 + 1, max.2),
             ),
-            Direction::YNegative => self.tree.search(
+            Direction::YNegative => self.tree().search(
                 Point::new(min.0, min.1 - 1, min.2),
                 Point::new(max.0, min.1, max.2),
             ),
-            Direction::ZPositive => self.tree.search(
+            Direction::ZPositive => self.tree().search(
                 Point::new(min.0, min.1, max.2),
                 Point::new(max.0, max.1, max.2 + 1),
             ),
-            Direction::ZNegative => self.tree.search(
+            Direction::ZNegative => self.tree().search(
                 Point::new(min.0, min.1, min.2 - 1),
                 Point::new(max.0, max.1, min.2),
             ),
-        };
-
-        indices
-            .into_iter()
-            .map(|idx| &self.data.bricks[idx])
-            .collect()
+        }
     }
 
     /// Fetch all bricks that bound a brick on one of its sides. This includes bricks that are partially
@@ -508,8 +757,196 @@ impl SaveOctree {
         self.bounds_side(min, max, dir)
     }
 
+    /// Get the indices of bricks that are buried more than `shell_thickness` layers deep.
+    /// A `shell_thickness` of 0 is treated as 1.
+    ///
+    /// A brick is covered when each of its six faces is entirely covered by a single other
+    /// brick. The outermost layer is made of every brick that is not covered; each further
+    /// layer is made of the bricks that are not covered once the outer layers are ignored.
+    pub fn interior_bricks(&self, shell_thickness: u32) -> Vec<usize> {
+        let bounds = self
+            .data
+            .bricks
+            .iter()
+            .map(|brick| self.brick_bounds(brick))
+            .collect::<Vec<_>>();
+        let mut remaining = vec![true; bounds.len()];
+
+        for _ in 0..cmp::max(shell_thickness, 1) {
+            let exposed = (0..bounds.len())
+                .filter(|&i| remaining[i] && !self.is_covered(i, &bounds, &remaining))
+                .collect::<Vec<_>>();
+            for i in exposed {
+                remaining[i] = false;
+            }
+        }
+
+        (0..bounds.len()).filter(|&i| remaining[i]).collect()
+    }
+
+    /// Check if every face of the brick at `index` is entirely covered by one of the `candidates`.
+    fn is_covered(&self, index: usize, bounds: &[BrickBounds], candidates: &[bool]) -> bool {
+        let (min, max) = bounds[index];
+        if !has_volume(min, max) {
+            return false;
+        }
+
+        DIRECTIONS.iter().all(|&dir| {
+            self.side_indices(min, max, dir).into_iter().any(|other| {
+                if other == index || !candidates[other] {
+                    return false;
+                }
+
+                let (omin, omax) = bounds[other];
+                let covers_x = omin.0 <= min.0 && omax.0 >= max.0;
+                let covers_y = omin.1 <= min.1 && omax.1 >= max.1;
+                let covers_z = omin.2 <= min.2 && omax.2 >= max.2;
+                match dir {
+                    Direction::XPositive | Direction::XNegative => covers_y && covers_z,
+                    Direction::YPositive | Direction::YNegative => covers_x && covers_z,
+                    Direction::ZPositive | Direction::ZNegative => covers_x && covers_y,
+                }
+            })
+        })
+    }
+
+    /// Fetch all bricks that share a face with `brick`, along with the direction of the face
+    /// they are on.
+    pub fn bricks_touching(&self, brick: &Brick) -> Vec<(Direction, &Brick)> {
+        self.touching_indices(brick)
+            .into_iter()
+            .map(|(dir, idx)| (dir, &self.data.bricks[idx]))
+            .collect()
+    }
+
+    /// Check if two bricks share a face.
+    pub fn are_touching(&self, a: &Brick, b: &Brick) -> bool {
+        let (amin, amax) = self.brick_bounds(a);
+        let (bmin, bmax) = self.brick_bounds(b);
+        let overlaps = [
+            amin.0 < bmax.0 && bmin.0 < amax.0,
+            amin.1 < bmax.1 && bmin.1 < amax.1,
+            amin.2 < bmax.2 && bmin.2 < amax.2,
+        ];
+        let touches = [
+            amax.0 == bmin.0 || bmax.0 == amin.0,
+            amax.1 == bmin.1 || bmax.1 == amin.1,
+            amax.2 == bmin.2 || bmax.2 == amin.2,
+        ];
+
+        (0..3).any(|axis| touches[axis] && (0..3).all(|o| o == axis || overlaps[o]))
+    }
+
+    /// Build an adjacency list of the bricks in the save, where entry `i` holds the sorted
+    /// indices of every brick touching brick `i`.
+    ///
+    /// The graph is symmetrical: if `j` is in entry `i`, then `i` is in entry `j`.
+    pub fn adjacency_graph(&self) -> Vec<Vec<usize>> {
+        let mut graph = vec![vec![]; self.data.bricks.len()];
+        for (i, brick) in self.data.bricks.iter().enumerate() {
+            for (_, j) in self.touching_indices(brick) {
+                graph[i].push(j);
+                graph[j].push(i);
+            }
+        }
+
+        for neighbors in graph.iter_mut() {
+            neighbors.sort_unstable();
+            neighbors.dedup();
+        }
+        graph
+    }
+
+    /// Build an adjacency list of the bricks in the save like [`adjacency_graph`](SaveOctree::adjacency_graph),
+    /// but only including the bricks touching each brick's face in `direction`.
+    pub fn adjacency_graph_directed(&self, direction: Direction) -> Vec<Vec<usize>> {
+        self.data
+            .bricks
+            .iter()
+            .map(|brick| {
+                self.touching_indices(brick)
+                    .into_iter()
+                    .filter(|&(dir, _)| dir == direction)
+                    .map(|(_, j)| j)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Get the indices of the bricks touching `brick`, along with the direction of the face they are on.
+    fn touching_indices(&self, brick: &Brick) -> Vec<(Direction, usize)> {
+        let (min, max) = self.brick_bounds(brick);
+        if !has_volume(min, max) {
+            return vec![];
+        }
+
+        let mut touching = vec![];
+        for &dir in DIRECTIONS.iter() {
+            let mut indices = self
+                .side_indices(min, max, dir)
+                .into_iter()
+                .filter(|&i| !std::ptr::eq(&self.data.bricks[i], brick))
+                .collect::<Vec<_>>();
+            indices.sort_unstable();
+            touching.extend(indices.into_iter().map(|i| (dir, i)));
+        }
+        touching
+    }
+
+    /// Iterate over every occupied chunk in the octree, in no particular order.
+    pub fn iter_chunks(&self) -> impl Iterator<Item = ChunkRef<'_>> {
+        self.tree()
+            .chunks
+            .iter()
+            .map(move |(&(x, y, z), node)| ChunkRef {
+                data: &self.data,
+                node,
+                chunk: Point::new(x, y, z),
+            })
+    }
+
     /// Return the inner `SaveData`, consuming this `SaveOctree`.
     pub fn into_inner(self) -> SaveData {
         self.data
     }
 }
+
+/// A borrowed chunk of a [`SaveOctree`], from [`SaveOctree::iter_chunks`].
+#[derive(Clone, Copy)]
+pub struct ChunkRef<'a> {
+    data: &'a SaveData,
+    node: &'a Node<usize>,
+    chunk: Point,
+}
+
+impl<'a> ChunkRef<'a> {
+    /// The position of the chunk, in chunks.
+    pub fn chunk(&self) -> Point {
+        self.chunk
+    }
+
+    /// Get the bounds of the chunk as two points in space.
+    pub fn bounds(&self) -> (Point, Point) {
+        let min = Point::new(
+            self.chunk.x * CHUNK_SIZE,
+            self.chunk.y * CHUNK_SIZE,
+            self.chunk.z * CHUNK_SIZE,
+        );
+        (min, min.shifted(CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE))
+    }
+
+    /// Get every brick in the chunk, in index order. Bricks that span several chunks are
+    /// included in each of them.
+    pub fn bricks(&self) -> Vec<&'a Brick> {
+        let (min, max) = self.bounds();
+        let mut set = HashSet::new();
+        self.node.search(min, max, &mut set);
+
+        let mut indices = set.into_iter().collect::<Vec<_>>();
+        indices.sort_unstable();
+        indices
+            .into_iter()
+            .map(|idx| &self.data.bricks[idx])
+            .collect()
+    }
+}
@@ -0,0 +1,129 @@
+//! Rigid transformations of whole saves.
+
+use crate::save::{Rotation, SaveData, TransformError};
+
+/// A transformation built up from translations, rotations, and mirrors, applied to a save
+/// all at once with [`apply`](Transform::apply).
+///
+/// Operations are applied in the order they are added.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    matrix: [[f32; 4]; 4],
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+impl Transform {
+    /// A transformation that does nothing.
+    pub fn identity() -> Self {
+        Transform {
+            matrix: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        }
+    }
+
+    /// A transformation from a row-major 4x4 matrix, in the form taken by
+    /// [`SaveData::apply_transform`].
+    pub fn from_matrix(matrix: [[f32; 4]; 4]) -> Self {
+        Transform { matrix }
+    }
+
+    /// The row-major 4x4 matrix of the transformation.
+    pub fn matrix(&self) -> &[[f32; 4]; 4] {
+        &self.matrix
+    }
+
+    /// Move by `(dx, dy, dz)`.
+    pub fn translate(self, dx: i32, dy: i32, dz: i32) -> Self {
+        let mut m = Transform::identity().matrix;
+        m[0][3] = dx as f32;
+        m[1][3] = dy as f32;
+        m[2][3] = dz as f32;
+        self.then(m)
+    }
+
+    /// Rotate counterclockwise around the X axis, from +Y towards +Z.
+    pub fn rotate_x(self, rotation: Rotation) -> Self {
+        self.rotate(rotation, 1, 2)
+    }
+
+    /// Rotate counterclockwise around the Y axis, from +Z towards +X.
+    pub fn rotate_y(self, rotation: Rotation) -> Self {
+        self.rotate(rotation, 2, 0)
+    }
+
+    /// Rotate counterclockwise around the Z axis, from +X towards +Y.
+    pub fn rotate_z(self, rotation: Rotation) -> Self {
+        self.rotate(rotation, 0, 1)
+    }
+
+    /// Mirror across the YZ plane, negating X.
+    ///
+    /// Bricks can't be mirrored, so a transformation with an odd number of mirrors fails to
+    /// apply with [`TransformError::UnsupportedRotation`].
+    pub fn mirror_x(self) -> Self {
+        self.mirror(0)
+    }
+
+    /// Mirror across the XZ plane, negating Y. See [`mirror_x`](Transform::mirror_x).
+    pub fn mirror_y(self) -> Self {
+        self.mirror(1)
+    }
+
+    /// Mirror across the XY plane, negating Z. See [`mirror_x`](Transform::mirror_x).
+    pub fn mirror_z(self) -> Self {
+        self.mirror(2)
+    }
+
+    /// Combine this transformation with `other`, which is applied after it.
+    pub fn compose(self, other: Transform) -> Self {
+        self.then(other.matrix)
+    }
+
+    /// Apply the transformation to every brick in `save`.
+    /// See [`SaveData::apply_transform`].
+    pub fn apply(&self, save: &mut SaveData) -> Result<(), TransformError> {
+        save.apply_transform(&self.matrix)
+    }
+
+    /// Rotate in the plane of axes `a` and `b`, from `a` towards `b`.
+    fn rotate(self, rotation: Rotation, a: usize, b: usize) -> Self {
+        let (cos, sin) = match rotation {
+            Rotation::Deg0 => (1.0, 0.0),
+            Rotation::Deg90 => (0.0, 1.0),
+            Rotation::Deg180 => (-1.0, 0.0),
+            Rotation::Deg270 => (0.0, -1.0),
+        };
+        let mut m = Transform::identity().matrix;
+        m[a][a] = cos;
+        m[a][b] = -sin;
+        m[b][a] = sin;
+        m[b][b] = cos;
+        self.then(m)
+    }
+
+    fn mirror(self, axis: usize) -> Self {
+        let mut m = Transform::identity().matrix;
+        m[axis][axis] = -1.0;
+        self.then(m)
+    }
+
+    /// Apply `next` after this transformation.
+    fn then(self, next: [[f32; 4]; 4]) -> Self {
+        let mut matrix = [[0.0f32; 4]; 4];
+        for (row, matrix_row) in matrix.iter_mut().enumerate() {
+            for (col, entry) in matrix_row.iter_mut().enumerate() {
+                *entry = (0..4).map(|k| next[row][k] * self.matrix[k][col]).sum();
+            }
+        }
+        Transform { matrix }
+    }
+}
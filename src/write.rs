@@ -6,17 +6,22 @@ use std::{
     io::{self, Write},
 };
 
+#[cfg(feature = "atomic-write")]
+use std::{fs::File, path::Path};
+
 use bitstream_io::{BitWrite, BitWriter};
 use byteorder::{LittleEndian, WriteBytesExt};
-use flate2::{write::ZlibEncoder, Compression};
+use flate2::write::ZlibEncoder;
 use thiserror::Error;
 
 use crate::{
     ext::*,
-    save::{BrickColor, SaveData, Size, UnrealType},
+    save::{BrickColor, Header1, SaveData, Size, UnrealType},
     MAGIC_BYTES, SAVE_VERSION,
 };
 
+pub use flate2::Compression;
+
 // bytes per brick used for initial allocation for brick bit vector
 // this is based on the minimum bits required to store a brick
 // the minimum bits required is 52, but we round up to 64 for reduced allocations in realistic cases
@@ -33,11 +38,49 @@ pub enum WriteError {
     BrickComponentMismatch,
 }
 
+/// The algorithm used to compress each section of a save.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    /// Standard zlib compression, as specified by the BRS format.
+    Zlib(Compression),
+    /// LZ4 block compression.
+    ///
+    /// This is a non-standard extension to the BRS format: compressed sections
+    /// are marked with a negative compressed size. Brickadia itself cannot read
+    /// saves written this way, so only use it for tool-to-tool workflows.
+    #[cfg(feature = "lz4")]
+    Lz4,
+}
+
+impl Default for CompressionAlgorithm {
+    fn default() -> Self {
+        CompressionAlgorithm::Zlib(Compression::default())
+    }
+}
+
+/// A section of a save, in the order they are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    Header0,
+    Header1,
+    Header2,
+    Preview,
+    Bricks,
+    Components,
+}
+
+/// A hook called with each section and the writer before the section is written.
+type SectionHook = Box<dyn Fn(Section, &mut dyn Write) -> io::Result<()>>;
+
 /// A save writer, which writes its `data` to its `writer` (a `Write`).
+///
+/// Saves are written as version [`SAVE_VERSION`](crate::SAVE_VERSION), which has no texture
+/// asset list, so `header2.texture_assets` is not written.
 pub struct SaveWriter<W: Write> {
     writer: W,
     data: SaveData,
-    compressed: bool,
+    compression: Option<CompressionAlgorithm>,
+    section_hook: Option<SectionHook>,
 }
 
 impl<W: Write> SaveWriter<W> {
@@ -45,7 +88,8 @@ impl<W: Write> SaveWriter<W> {
         SaveWriter {
             writer,
             data,
-            compressed: true,
+            compression: Some(CompressionAlgorithm::default()),
+            section_hook: None,
         }
     }
 
@@ -53,13 +97,34 @@ impl<W: Write> SaveWriter<W> {
         SaveWriter {
             writer,
             data,
-            compressed: false,
+            compression: None,
+            section_hook: None,
         }
     }
 
+    /// Compress sections using `algorithm` instead of the default zlib compression.
+    pub fn with_algorithm(mut self, algorithm: CompressionAlgorithm) -> SaveWriter<W> {
+        self.compression = Some(algorithm);
+        self
+    }
+
+    /// Call `hook` with each section and the underlying writer just before the section is
+    /// written, e.g. to inject extra data between sections.
+    ///
+    /// Anything written by the hook ends up in the save as-is, so it must be data that
+    /// whatever reads the save knows to expect.
+    pub fn with_section_hook<F>(mut self, hook: F) -> SaveWriter<W>
+    where
+        F: Fn(Section, &mut dyn Write) -> io::Result<()> + 'static,
+    {
+        self.section_hook = Some(Box::new(hook));
+        self
+    }
+
     pub fn write(mut self) -> Result<(), WriteError> {
         // write header 0
         {
+            run_section_hook(&self.section_hook, Section::Header0, &mut self.writer)?;
             self.writer.write_all(MAGIC_BYTES)?;
             self.writer.write_u16::<LittleEndian>(SAVE_VERSION)?;
             self.writer
@@ -74,24 +139,9 @@ impl<W: Write> SaveWriter<W> {
 
         // write header 1
         {
-            // this Vec<u8> will store the bytes to the header, and eventually
-            // will be compressed when necessary
-            let mut w: Vec<u8> = vec![];
-            w.write_string(self.data.header1.map)?;
-            w.write_string(self.data.header1.author.name.to_owned())?;
-            w.write_string(self.data.header1.description)?;
-            w.write_uuid(self.data.header1.author.id)?;
-
-            // if the host is None, then we assume it to be the
-            // same as the author. can safely write the same value
-            let host = self.data.header1.host.unwrap_or(self.data.header1.author);
-            w.write_string(host.name)?;
-            w.write_uuid(host.id)?;
-
-            w.write_datetime(self.data.header1.save_time)?;
-            w.write_i32::<LittleEndian>(self.data.bricks.len() as i32)?;
-
-            write_compressed(&mut self.writer, w, self.compressed)?;
+            let w = header1_bytes(&self.data.header1, brick_count)?;
+            run_section_hook(&self.section_hook, Section::Header1, &mut self.writer)?;
+            write_compressed(&mut self.writer, w, self.compression)?;
         }
 
         // write header 2
@@ -99,41 +149,44 @@ impl<W: Write> SaveWriter<W> {
             // see above for compression methods
             let mut w: Vec<u8> = vec![];
 
-            w.write_array(self.data.header2.mods, |writer, string| {
-                writer.write_string(string)
+            let header2 = &self.data.header2;
+            w.write_array_ref(&header2.mods, |writer, string| {
+                writer.write_string_ref(string)
             })?;
 
-            w.write_array(self.data.header2.brick_assets, |writer, string| {
-                writer.write_string(string)
+            w.write_array_ref(&header2.brick_assets, |writer, string| {
+                writer.write_string_ref(string)
             })?;
 
-            w.write_array(self.data.header2.colors, |writer, color| {
+            w.write_array_ref(&header2.colors, |writer, color| {
                 writer.write_color_bgra(color)
             })?;
 
-            w.write_array(self.data.header2.materials, |writer, string| {
-                writer.write_string(string)
+            w.write_array_ref(&header2.materials, |writer, string| {
+                writer.write_string_ref(string)
             })?;
 
-            w.write_array(
-                self.data.header2.brick_owners,
+            w.write_array_ref(
+                &header2.brick_owners,
                 |writer, brick_owner| -> io::Result<()> {
                     writer.write_uuid(brick_owner.id)?;
-                    writer.write_string(brick_owner.name)?;
+                    writer.write_string_ref(&brick_owner.name)?;
                     writer.write_i32::<LittleEndian>(brick_owner.bricks as i32)?;
                     Ok(())
                 },
             )?;
 
-            w.write_array(self.data.header2.physical_materials, |writer, string| {
-                writer.write_string(string)
+            w.write_array_ref(&header2.physical_materials, |writer, string| {
+                writer.write_string_ref(string)
             })?;
 
-            write_compressed(&mut self.writer, w, self.compressed)?;
+            run_section_hook(&self.section_hook, Section::Header2, &mut self.writer)?;
+            write_compressed(&mut self.writer, w, self.compression)?;
         }
 
         // write preview
         {
+            run_section_hook(&self.section_hook, Section::Preview, &mut self.writer)?;
             let preview_type = self.data.preview.type_byte();
             self.writer.write_u8(preview_type)?;
             match preview_type {
@@ -234,7 +287,8 @@ impl<W: Write> SaveWriter<W> {
 
             bits.byte_align()?;
 
-            write_compressed(&mut self.writer, vec, self.compressed)?;
+            run_section_hook(&self.section_hook, Section::Bricks, &mut self.writer)?;
+            write_compressed(&mut self.writer, vec, self.compression)?;
 
             let mut vec: Vec<u8> = vec![];
             vec.write_i32::<LittleEndian>(component_bricks.len() as i32)?;
@@ -245,7 +299,7 @@ impl<W: Write> SaveWriter<W> {
                     None => return Err(WriteError::BrickComponentMismatch),
                 };
 
-                vec.write_string(name.to_owned())?;
+                vec.write_string_ref(&name)?;
 
                 let mut bits = BitWriter::endian(Vec::new(), bitstream_io::LittleEndian);
 
@@ -261,8 +315,8 @@ impl<W: Write> SaveWriter<W> {
                 let properties = component.properties.into_iter().collect::<Vec<_>>();
 
                 bits.write_array(&properties, |writer, (key, val)| -> io::Result<()> {
-                    writer.write_string(key.clone())?;
-                    writer.write_string(val.clone())?;
+                    writer.write_string_ref(key)?;
+                    writer.write_string_ref(val)?;
                     Ok(())
                 })?;
 
@@ -281,33 +335,113 @@ impl<W: Write> SaveWriter<W> {
                 vec.extend(bit_vec.into_iter());
             }
 
-            write_compressed(&mut self.writer, vec, self.compressed)?;
+            run_section_hook(&self.section_hook, Section::Components, &mut self.writer)?;
+            write_compressed(&mut self.writer, vec, self.compression)?;
         }
 
         Ok(())
     }
 }
 
+#[cfg(feature = "atomic-write")]
+impl SaveWriter<File> {
+    /// Write `data` to the file at `path` atomically.
+    ///
+    /// The save is first written to a temporary file in the same directory as `path`, which
+    /// is then renamed over `path` once writing succeeds. If writing fails, the temporary file
+    /// is deleted and `path` is left untouched.
+    pub fn write_atomic(path: impl AsRef<Path>, data: SaveData) -> Result<(), WriteError> {
+        let path = path.as_ref();
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        let mut temp = tempfile::NamedTempFile::new_in(dir)?;
+        SaveWriter::new(temp.as_file_mut(), data).write()?;
+        temp.as_file().sync_all()?;
+
+        match temp.persist(path) {
+            Ok(_) => Ok(()),
+            // renaming over an existing file can fail on Windows,
+            // so remove the destination and try again
+            Err(err) if cfg!(windows) && path.exists() => {
+                std::fs::remove_file(path)?;
+                err.file.persist(path).map_err(|err| err.error)?;
+                Ok(())
+            }
+            Err(err) => Err(err.error.into()),
+        }
+    }
+}
+
+/// Serialize the first header, before compression, recording `brick_count` bricks.
+pub(crate) fn header1_bytes(header1: &Header1, brick_count: usize) -> io::Result<Vec<u8>> {
+    let mut w: Vec<u8> = vec![];
+    w.write_string_ref(&header1.map)?;
+    w.write_string_ref(&header1.author.name)?;
+    w.write_string_ref(&header1.description)?;
+    w.write_uuid(header1.author.id)?;
+
+    // if the host is None, then we assume it to be the
+    // same as the author. can safely write the same value
+    let host = header1.host.as_ref().unwrap_or(&header1.author);
+    w.write_string_ref(&host.name)?;
+    w.write_uuid(host.id)?;
+
+    w.write_datetime(header1.save_time)?;
+    w.write_i32::<LittleEndian>(brick_count as i32)?;
+    Ok(w)
+}
+
+/// Call the section hook, if there is one, before writing `section` to `writer`.
+fn run_section_hook(
+    hook: &Option<SectionHook>,
+    section: Section,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    match hook {
+        Some(hook) => hook(section, writer),
+        None => Ok(()),
+    }
+}
+
 /// Write a `Vec<u8>` out to a `Write`, following the BRS spec for compression.
 fn write_compressed(
     writer: &mut impl Write,
     vec: Vec<u8>,
-    should_compress: bool,
+    compression: Option<CompressionAlgorithm>,
 ) -> io::Result<()> {
-    if !should_compress {
-        writer.write_i32::<LittleEndian>(vec.len() as i32)?;
-        writer.write_i32::<LittleEndian>(0)?;
-        writer.write_all(&vec[..])?;
-        return Ok(());
-    }
+    let algorithm = match compression {
+        Some(algorithm) => algorithm,
+        None => {
+            writer.write_i32::<LittleEndian>(vec.len() as i32)?;
+            writer.write_i32::<LittleEndian>(0)?;
+            writer.write_all(&vec[..])?;
+            return Ok(());
+        }
+    };
 
-    let compressed = ZlibEncoder::new(vec.clone(), Compression::default()).finish()?;
+    let (compressed, size) = match algorithm {
+        CompressionAlgorithm::Zlib(level) => {
+            let compressed = ZlibEncoder::new(vec.clone(), level).finish()?;
+            let size = compressed.len() as i32;
+            (compressed, size)
+        }
+        #[cfg(feature = "lz4")]
+        CompressionAlgorithm::Lz4 => {
+            // lz4 sections are flagged with a negative compressed size
+            let compressed = lz4_flex::block::compress(&vec[..]);
+            let size = -(compressed.len() as i32);
+            (compressed, size)
+        }
+    };
 
     writer.write_i32::<LittleEndian>(vec.len() as i32)?;
 
     if compressed.len() < vec.len() {
         // compressed is smaller, write (unc_size: i32, c_size: i32, bytes)
-        writer.write_i32::<LittleEndian>(compressed.len() as i32)?;
+        writer.write_i32::<LittleEndian>(size)?;
         writer.write_all(&compressed[..])?;
     } else {
         // write uncompressed (unc_size: i32, c_size: i32 = 0, bytes)
@@ -0,0 +1,234 @@
+use brickadia::save::*;
+
+#[test]
+fn packed_u32_round_trip() {
+    let color = Color {
+        r: 1,
+        g: 2,
+        b: 3,
+        a: 4,
+    };
+    assert_eq!(color.to_u32_rgba(), 0x01020304);
+    assert_eq!(color.to_u32_argb(), 0x04010203);
+    assert_eq!(color.to_u32_bgra(), 0x03020104);
+    assert_eq!(Color::from_u32_rgba(color.to_u32_rgba()), color);
+    assert_eq!(Color::from_u32_argb(color.to_u32_argb()), color);
+    assert_eq!(Color::from_u32_bgra(color.to_u32_bgra()), color);
+
+    let packed: u32 = color.clone().into();
+    assert_eq!(Color::from(packed), color);
+}
+
+#[cfg(feature = "util")]
+#[test]
+fn gradients_have_requested_length_and_endpoints() {
+    use brickadia::util::color::*;
+
+    let start = Color::from_u32_rgba(0x000000ff);
+    let end = Color::from_u32_rgba(0xff6432ff);
+    let gradient = generate_gradient(start.clone(), end.clone(), 5);
+    assert_eq!(gradient.len(), 5);
+    assert_eq!(gradient[0], start);
+    assert_eq!(gradient[4], end);
+
+    let gradient = generate_gradient_linear(start.clone(), end.clone(), 7);
+    assert_eq!(gradient.len(), 7);
+    assert_eq!(gradient[0], start);
+    assert_eq!(gradient[6], end);
+
+    let rainbow = generate_palette_rainbow(6);
+    assert_eq!(rainbow.len(), 6);
+    assert_eq!(rainbow[0], Color::from_u32_rgba(0xff0000ff));
+    assert_eq!(rainbow[2], Color::from_u32_rgba(0x00ff00ff));
+
+    let mut header2 = Header2::default();
+    let mut bricks = (0..10)
+        .map(|i| Brick {
+            position: (i * 10, 0, 0),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    apply_gradient_to_bricks(&mut bricks, &mut header2, gradient, Axis::X);
+    assert_eq!(bricks[0].color, BrickColor::Index(0));
+    assert_eq!(bricks[9].color, BrickColor::Index(6));
+}
+
+#[cfg(feature = "util")]
+#[test]
+fn palettes_sort_by_hue_and_luminance() {
+    use brickadia::util::color::*;
+
+    let blue = Color::from_u32_rgba(0x0000ffff);
+    let red = Color::from_u32_rgba(0xff0000ff);
+    let green = Color::from_u32_rgba(0x00ff00ff);
+    let yellow = Color::from_u32_rgba(0xffff00ff);
+    let grey = Color::from_u32_rgba(0x808080ff);
+    assert_eq!(blue.hue(), 240.0);
+    assert_eq!(Color::from_u32_rgba(0xff00ffff).hue(), 300.0);
+    assert!(Color::from_u32_rgba(0xffffffff).luminance() > 0.999);
+
+    let mut palette = vec![
+        blue.clone(),
+        red.clone(),
+        green.clone(),
+        yellow.clone(),
+        grey.clone(),
+    ];
+    let mut bricks = [0, 4]
+        .into_iter()
+        .map(|color| Brick {
+            color: BrickColor::Index(color),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    let mapping = sort_palette_by_hue(&mut palette);
+    assert_eq!(
+        palette,
+        vec![red, grey, yellow.clone(), green, blue.clone()]
+    );
+    apply_palette_mapping(&mut bricks, &mapping);
+    assert_eq!(bricks[0].color, BrickColor::Index(4));
+    assert_eq!(bricks[1].color, BrickColor::Index(1));
+
+    sort_palette_by_luminance(&mut palette);
+    assert_eq!(palette[0], blue);
+    assert_eq!(palette[4], yellow);
+
+    // colors order by their components, red first
+    assert!(Color::from_u32_rgba(0x01000000) > Color::from_u32_rgba(0x00ffffff));
+}
+
+#[test]
+fn brick_colors_parse_every_form() {
+    let unique = BrickColor::Unique(Color {
+        r: 0x12,
+        g: 0xab,
+        b: 0,
+        a: 0xff,
+    });
+    assert_eq!(unique.to_string(), "Unique(#12ab00ff)");
+    assert_eq!(BrickColor::Index(3).to_string(), "Index(3)");
+
+    assert_eq!("0".parse(), Ok(BrickColor::Index(0)));
+    assert_eq!(" 3 ".parse(), Ok(BrickColor::Index(3)));
+    assert_eq!("Index(7)".parse(), Ok(BrickColor::Index(7)));
+    assert_eq!("#12AB00ff".parse(), Ok(unique.clone()));
+    assert_eq!("[18, 171,0,255]".parse(), Ok(unique.clone()));
+    assert_eq!("Unique(#12ab00ff)".parse(), Ok(unique.clone()));
+    assert_eq!(unique.to_string().parse(), Ok(unique));
+
+    let err = |s: &str| s.parse::<BrickColor>().unwrap_err();
+    assert_eq!(err("-1"), ParseBrickColorError::InvalidIndex);
+    assert_eq!(err("Index(#12ab00ff)"), ParseBrickColorError::InvalidIndex);
+    assert_eq!(err("#12ab00"), ParseBrickColorError::InvalidHex);
+    assert_eq!(err("#+2ab00ff"), ParseBrickColorError::InvalidHex);
+    assert_eq!(err("Unique(3)"), ParseBrickColorError::InvalidHex);
+    assert_eq!(err("[1,2,3]"), ParseBrickColorError::InvalidArray);
+    assert_eq!(err("[1,2,3,256]"), ParseBrickColorError::InvalidArray);
+}
+
+#[cfg(feature = "serialize")]
+#[test]
+fn brick_colors_serialize_like_brs_js() {
+    let brick = |color| Brick {
+        color,
+        ..Default::default()
+    };
+    let json = serde_json::to_value(brick(BrickColor::Index(3))).unwrap();
+    assert_eq!(json["color"], serde_json::json!(3));
+    let json = serde_json::to_value(brick(BrickColor::Unique(Color::from_u32_rgba(0x12ab00ff))));
+    assert_eq!(json.unwrap()["color"], serde_json::json!([0x12, 0xab, 0]));
+}
+
+#[test]
+fn red_is_closer_to_orange_than_blue() {
+    let rgba = |r, g, b, a| Color { r, g, b, a };
+    let red = rgba(255, 0, 0, 255);
+    let orange = rgba(255, 165, 0, 255);
+    let blue = rgba(0, 0, 255, 255);
+    assert!(red.distance_to_euclidean(&orange) < red.distance_to_euclidean(&blue));
+    assert!(red.distance_to_cie76(&orange) < red.distance_to_cie76(&blue));
+
+    // alpha only counts in distance_to_rgba
+    let origin = rgba(0, 0, 0, 0);
+    assert!((origin.distance_to_euclidean(&rgba(3, 4, 0, 100)) - 5.0).abs() < 1e-5);
+    assert!((origin.distance_to_rgba(&rgba(0, 3, 0, 4)) - 5.0).abs() < 1e-5);
+    // black and white are 100 apart in L*
+    let white = rgba(255, 255, 255, 255);
+    assert!((white.distance_to_cie76(&rgba(0, 0, 0, 255)) - 100.0).abs() < 0.1);
+
+    let palette = [blue, orange.clone(), orange];
+    assert_eq!(red.nearest_in_palette(&palette).unwrap().0, 1);
+    assert!(red.nearest_in_palette(&[]).is_none());
+}
+
+#[test]
+fn hsl_round_trips_within_one() {
+    let red = Color::from_u32_rgba(0xff0000ff);
+    assert_eq!(Color::from_hsl(0.0, 1.0, 0.5, 255), red);
+    assert_eq!(Color::from_hsl(360.0, 1.0, 0.5, 255), red);
+    assert_eq!(red.to_hsl(), (0.0, 1.0, 0.5));
+    assert_eq!(red.to_hsl_string(), "hsl(0, 100%, 50%)");
+    assert_eq!(
+        Color::from_hsl(120.0, 1.0, 0.5, 7),
+        Color::from_u32_rgba(0x00ff0007)
+    );
+    assert_eq!(Color::from_u32_rgba(0x808080ff).to_hsl().1, 0.0);
+
+    let mut seed: u32 = 12345;
+    for _ in 0..1000 {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        let color = Color::from_u32_rgba(seed);
+        let (h, s, l) = color.to_hsl();
+        assert!((0.0..360.0).contains(&h));
+        assert!((0.0..=1.0).contains(&s) && (0.0..=1.0).contains(&l));
+
+        let back = Color::from_hsl(h, s, l, color.a);
+        for (a, b) in [(color.r, back.r), (color.g, back.g), (color.b, back.b)] {
+            assert!(a.abs_diff(b) <= 1, "{:?} became {:?}", color, back);
+        }
+        assert_eq!(back.a, color.a);
+    }
+}
+
+#[cfg(feature = "util")]
+#[test]
+fn median_cut_palette_sizes() {
+    use brickadia::util::color_quantize::*;
+
+    let mut seed: u32 = 7;
+    let colors = (0..500)
+        .map(|_| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            Color::from_u32_rgba(seed | 0xff)
+        })
+        .collect::<Vec<_>>();
+    for size in [1, 2, 7, 16, 64] {
+        assert_eq!(median_cut_quantize(&colors, size).len(), size);
+    }
+
+    // fewer colors come back when there aren't enough distinct colors
+    assert!(median_cut_quantize(&[], 4).is_empty());
+    let grey = Color::from_u32_rgba(0x808080ff);
+    assert_eq!(median_cut_quantize(&vec![grey.clone(); 10], 4), vec![grey]);
+
+    let mut save = SaveData::default();
+    save.header2.colors = vec![Color::from_u32_rgba(0xff0000ff)];
+    for color in &colors {
+        save.bricks.push(Brick {
+            color: BrickColor::Unique(color.clone()),
+            ..Default::default()
+        });
+    }
+    save.bricks.push(Brick {
+        color: BrickColor::Index(0),
+        ..Default::default()
+    });
+    quantize_save_colors(&mut save, 16);
+    assert!(save.header2.colors.len() <= 17);
+    assert!(save.bricks.iter().all(|brick| matches!(
+        brick.color,
+        BrickColor::Index(i) if (i as usize) < save.header2.colors.len()
+    )));
+    assert_eq!(save.bricks.last().unwrap().color, BrickColor::Index(0));
+}
@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+
+use brickadia::{
+    read::{ReadError, SaveReader},
+    save::*,
+    write::SaveWriter,
+};
+
+/// Write `save` and read it back.
+fn round_trip(save: &SaveData) -> SaveData {
+    let mut bytes = vec![];
+    SaveWriter::new(&mut bytes, save.clone()).write().unwrap();
+    SaveReader::new(&bytes[..]).unwrap().read_all().unwrap()
+}
+
+#[cfg(feature = "atomic-write")]
+#[test]
+fn write_atomic_keeps_destination_on_failure() {
+    use std::fs;
+
+    let dir = std::env::temp_dir().join(format!("brickadia-atomic-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("save.brs");
+    let mut save = SaveData::default();
+    save.bricks.push(Brick::default());
+
+    SaveWriter::write_atomic(&path, save.clone()).unwrap();
+    let mut reader = SaveReader::new(fs::File::open(&path).unwrap()).unwrap();
+    assert_eq!(reader.read_all().unwrap().bricks, save.bricks);
+
+    // an out of range asset index fails partway through the brick section
+    fs::write(&path, b"old").unwrap();
+    save.bricks[0].asset_name_index = 99;
+    assert!(SaveWriter::write_atomic(&path, save).is_err());
+    assert_eq!(fs::read(&path).unwrap(), b"old");
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn voxel_grid_round_trips() {
+    // `occupied` is shorter than the grid; missing cells are empty
+    let grid = VoxelGrid {
+        width: 3,
+        depth: 3,
+        height: 2,
+        occupied: vec![true; 5],
+        colors: vec![],
+    };
+    for merge in [false, true] {
+        let config = VoxelImportConfig {
+            merge,
+            ..Default::default()
+        };
+        let save = SaveData::from_voxel_grid(&grid, config);
+        let cells = save
+            .bricks
+            .iter()
+            .map(|brick| brick.volume() / (5 * 5 * 6))
+            .sum::<u64>();
+        assert_eq!(cells, 5);
+
+        let read = round_trip(&save);
+        assert_eq!(read.bricks, save.bricks);
+        assert_eq!(read.header2.colors, save.header2.colors);
+    }
+}
+
+#[test]
+fn estimate_file_size_is_within_2x() {
+    let example = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/read.brs");
+    let example = SaveReader::new(std::fs::File::open(example).unwrap())
+        .unwrap()
+        .read_all()
+        .unwrap();
+
+    let mut grid = SaveData::default();
+    for x in 0..100 {
+        for y in 0..100 {
+            grid.bricks.push(Brick {
+                position: (x * 10, y * 10, 6),
+                size: Size::Procedural(5, 5, 6),
+                ..Default::default()
+            });
+        }
+    }
+
+    let mut scattered = SaveData::default();
+    let mut seed = 12345u32;
+    for _ in 0..20000 {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        scattered.bricks.push(Brick {
+            position: (
+                (seed % 100000) as i32 - 50000,
+                ((seed >> 8) % 100000) as i32,
+                (seed % 3000) as i32,
+            ),
+            size: Size::Procedural(seed % 50, seed % 70, seed % 20),
+            color: BrickColor::Unique(Color::from_u32_rgba(seed | 0xff)),
+            ..Default::default()
+        });
+    }
+
+    for save in [example, grid, scattered] {
+        let estimate = save.estimate_file_size();
+        let mut bytes = vec![];
+        SaveWriter::new(&mut bytes, save).write().unwrap();
+        assert!(estimate <= bytes.len() * 2 && bytes.len() <= estimate * 2);
+    }
+}
+
+#[test]
+fn merged_components_round_trip() {
+    let lit = |brightness: f32, properties: &[(&str, &str)]| {
+        let mut save = SaveData::default();
+        let mut brick = Brick::default();
+        brick.add_component_property(
+            "BCD_PointLight",
+            "Brightness",
+            UnrealType::Float(brightness),
+        );
+        save.bricks.push(brick);
+        save.components.insert(
+            "BCD_PointLight".into(),
+            Component {
+                brick_indices: vec![0],
+                properties: properties
+                    .iter()
+                    .map(|&(name, ty)| (name.to_string(), ty.to_string()))
+                    .collect::<HashMap<_, _>>(),
+                ..Default::default()
+            },
+        );
+        save
+    };
+
+    let mut save = lit(1.0, &[("Brightness", "Float")]);
+    save.merge(lit(2.0, &[("Brightness", "Float")])).unwrap();
+    assert_eq!(save.components["BCD_PointLight"].brick_indices, vec![0, 1]);
+    let read = round_trip(&save);
+    assert_eq!(read.bricks, save.bricks);
+    assert_eq!(read.components, save.components);
+
+    // properties that don't match are rejected rather than written without values
+    let before = save.clone();
+    let other = lit(3.0, &[("Brightness", "Float"), ("Radius", "Float")]);
+    assert_eq!(
+        save.merge(other),
+        Err(MergeError::ComponentPropertyMismatch(
+            "BCD_PointLight".into()
+        ))
+    );
+    let other = lit(3.0, &[("Brightness", "Byte")]);
+    assert!(save.merge(other).is_err());
+    assert_eq!(save.bricks, before.bricks);
+    assert_eq!(save.components, before.components);
+}
+
+#[test]
+fn linear_color_round_trips() {
+    let mut save = SaveData::default();
+    let mut brick = Brick::default();
+    let color = UnrealType::LinearColor(2.5, 0.5, 0.0, 1.0);
+    brick.add_component_property("BCD_PointLight", "Color", color);
+    save.bricks.push(brick);
+    save.components.insert(
+        "BCD_PointLight".into(),
+        Component {
+            version: 1,
+            brick_indices: vec![0],
+            properties: HashMap::from([("Color".to_string(), "LinearColor".to_string())]),
+        },
+    );
+
+    let read = round_trip(&save);
+    let color = &read.bricks[0].components["BCD_PointLight"]["Color"];
+    assert_eq!(color.try_as_linear_color(), Some((2.5, 0.5, 0.0, 1.0)));
+    assert_eq!(read.components, save.components);
+}
+
+#[test]
+fn header2_with_assets_round_trips() {
+    let header2 = Header2::with_assets(
+        vec!["PB_DefaultBrick".into(), "PB_DefaultTile".into()],
+        vec!["BMC_Glow".into()],
+        vec!["BPMC_Ice".into()],
+    );
+    let mut save = SaveData {
+        header2: header2.clone(),
+        ..Default::default()
+    };
+    save.bricks.push(Brick {
+        asset_name_index: 1,
+        ..Default::default()
+    });
+
+    let read = round_trip(&save);
+    assert_eq!(read.header2.brick_assets, header2.brick_assets);
+    assert_eq!(read.header2.materials, header2.materials);
+    assert_eq!(read.header2.physical_materials, header2.physical_materials);
+    assert_eq!(read.bricks[0].asset_name_index, 1);
+}
+
+#[cfg(feature = "util")]
+#[test]
+fn patch_header1_rewrites_saves_that_grow() {
+    use std::fs;
+
+    use brickadia::util::io::{patch_header1, Header1Patch};
+
+    let dir = std::env::temp_dir().join(format!("brickadia-patch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("save.brs");
+    let mut save = SaveData::default();
+    save.bricks.push(Brick::default());
+    save.repair_brick_count();
+    let mut bytes = vec![];
+    SaveWriter::new(&mut bytes, save.clone()).write().unwrap();
+    fs::write(&path, bytes).unwrap();
+
+    // a description this long can't fit in place, so the whole save is rewritten
+    let description = "a much longer description ".repeat(100);
+    let patch = Header1Patch {
+        description: Some(description.clone()),
+        ..Default::default()
+    };
+    patch_header1(&path, patch).unwrap();
+
+    let mut reader = SaveReader::new(fs::File::open(&path).unwrap()).unwrap();
+    let read = reader.read_all().unwrap();
+    assert_eq!(read.header1.description, description);
+    assert_eq!(read.bricks, save.bricks);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "util")]
+#[test]
+fn patch_header1_pads_saves_that_shrink() {
+    use std::fs;
+
+    use brickadia::util::io::{patch_header1, Header1Patch};
+
+    let dir = std::env::temp_dir().join(format!("brickadia-shrink-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("save.brs");
+    let mut save = SaveData::default();
+    save.header1.description = "a description that is about to get shorter".into();
+    save.bricks.push(Brick::default());
+    save.repair_brick_count();
+    let mut bytes = vec![];
+    SaveWriter::new(&mut bytes, save.clone()).write().unwrap();
+    fs::write(&path, &bytes).unwrap();
+
+    let patch = Header1Patch {
+        description: Some("short".into()),
+        ..Default::default()
+    };
+    patch_header1(&path, patch).unwrap();
+
+    // header1 starts after the magic bytes, version, and game version, and the padding is
+    // stored uncompressed
+    let patched = fs::read(&path).unwrap();
+    assert_eq!(patched.len(), bytes.len());
+    assert_eq!(patched[13..17], 0i32.to_le_bytes());
+    let read = SaveReader::new(&patched[..]).unwrap().read_all().unwrap();
+    assert_eq!(read.header1.description, "short");
+    assert_eq!(read.header1.brick_count, 1);
+    assert_eq!(read.bricks, save.bricks);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "util")]
+#[test]
+fn patch_header1_compresses_headers_that_grow() {
+    use std::fs;
+
+    use brickadia::util::io::{patch_header1, Header1Patch};
+
+    let dir = std::env::temp_dir().join(format!("brickadia-compress-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("save.brs");
+    // an incompressible description, replaced by a longer one that compresses well
+    let mut seed = 1u32;
+    let noise = (0..300)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (b'a' + (seed >> 16) as u8 % 26) as char
+        })
+        .collect::<String>();
+    let mut save = SaveData::default();
+    save.header1.description = noise;
+    save.bricks.push(Brick::default());
+    save.repair_brick_count();
+    let mut bytes = vec![];
+    SaveWriter::new(&mut bytes, save.clone()).write().unwrap();
+    fs::write(&path, &bytes).unwrap();
+
+    let description = "a".repeat(400);
+    let patch = Header1Patch {
+        description: Some(description.clone()),
+        ..Default::default()
+    };
+    patch_header1(&path, patch).unwrap();
+
+    let patched = fs::read(&path).unwrap();
+    assert_eq!(patched.len(), bytes.len());
+    assert_ne!(patched[13..17], 0i32.to_le_bytes());
+    let read = SaveReader::new(&patched[..]).unwrap().read_all().unwrap();
+    assert_eq!(read.header1.description, description);
+    assert_eq!(read.bricks, save.bricks);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn newer_versions_are_rejected_unless_allowed() {
+    let mut bytes = b"BRS".to_vec();
+    bytes.extend(9999u16.to_le_bytes());
+    bytes.extend(0i32.to_le_bytes());
+    assert!(matches!(
+        SaveReader::new(&bytes[..]),
+        Err(ReadError::UnrecognizedVersion {
+            found: 9999,
+            max_supported: 10,
+        })
+    ));
+    assert!(SaveReader::new_allow_newer_versions(&bytes[..]).is_ok());
+
+    // a version 11 save laid out like version 10 reads fine when allowed
+    let mut bytes = vec![];
+    SaveWriter::new(&mut bytes, SaveData::default())
+        .write()
+        .unwrap();
+    bytes[3..5].copy_from_slice(&11u16.to_le_bytes());
+    assert!(SaveReader::new(&bytes[..]).is_err());
+    let mut reader = SaveReader::new_allow_newer_versions(&bytes[..]).unwrap();
+    assert_eq!(reader.version, 11);
+    reader.read_all().unwrap();
+}
+
+#[test]
+fn texture_assets_are_not_written() {
+    let mut save = SaveData::default();
+    save.header2.texture_assets = vec!["TA_Grass".into()];
+    assert!(round_trip(&save).header2.texture_assets.is_empty());
+}
+
+#[cfg(feature = "test-utils")]
+#[test]
+fn saves_equivalent_compares_texture_assets() {
+    use brickadia::test_utils::saves_equivalent;
+
+    let save = SaveData::default();
+    let mut textured = save.clone();
+    textured.header2.texture_assets = vec!["TA_Grass".into()];
+    assert!(saves_equivalent(&save, &save.clone()));
+    assert!(!saves_equivalent(&save, &textured));
+}
+
+#[test]
+fn indices_round_trip_for_every_palette_size() {
+    for n in [1u32, 2, 3, 4, 5, 7, 8, 9, 255, 256, 257] {
+        let mut save = SaveData::default();
+        save.header2.colors = (0..n).map(Color::from_u32_rgba).collect();
+        save.header2.brick_assets = (0..n).map(|i| format!("PB_Asset{}", i)).collect();
+        save.header2.materials = (0..n).map(|i| format!("BMC_Material{}", i)).collect();
+        save.bricks = (0..n)
+            .map(|i| Brick {
+                asset_name_index: i,
+                material_index: n - 1 - i,
+                color: BrickColor::Index(i),
+                ..Default::default()
+            })
+            .collect();
+
+        let read = round_trip(&save);
+        let indices = |save: &SaveData| {
+            save.bricks
+                .iter()
+                .map(|b| (b.asset_name_index, b.material_index, b.color.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(indices(&read), indices(&save), "{} entries", n);
+    }
+}
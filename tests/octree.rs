@@ -0,0 +1,318 @@
+#![cfg(feature = "util")]
+
+use brickadia::{save::*, util::octree::*};
+
+/// A save with a solid `n`×`n`×`n` cube of 1x1 bricks, owned by a single owner.
+fn cube(n: i32) -> SaveData {
+    let mut save = SaveData::default();
+    save.header2
+        .brick_owners
+        .push(BrickOwner::from(User::default()));
+    for x in 0..n {
+        for y in 0..n {
+            for z in 0..n {
+                save.bricks.push(Brick {
+                    position: (x * 10 + 5, y * 10 + 5, z * 12 + 6),
+                    size: Size::Procedural(5, 5, 6),
+                    owner_index: 1,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+    save
+}
+
+#[test]
+fn hollow_cube() {
+    let mut save = cube(5);
+    assert_eq!(save.count_interior_bricks(), 27);
+    save.hollow(1);
+    assert_eq!(save.bricks.len(), 125 - 27);
+    assert_eq!(save.header1.brick_count, 125 - 27);
+    assert_eq!(save.header2.brick_owners[0].bricks, 125 - 27);
+
+    let mut save = cube(7);
+    save.hollow(2);
+    assert_eq!(save.bricks.len(), 343 - 27);
+}
+
+#[test]
+fn flat_bricks_are_never_interior() {
+    // a flat brick between the bottom and middle layers, covered on every side
+    let mut save = cube(3);
+    save.bricks.push(Brick {
+        position: (15, 15, 12),
+        size: Size::Procedural(5, 5, 0),
+        ..Default::default()
+    });
+    assert_eq!(save.count_interior_bricks(), 1);
+}
+
+#[test]
+fn split_at_divides_a_grid() {
+    use brickadia::util::octree::SaveOctree;
+
+    let mut save = SaveData::default();
+    for x in 0..10 {
+        for y in 0..10 {
+            save.bricks.push(Brick {
+                position: (x * 10 + 5, y * 10 + 5, 6),
+                size: Size::Procedural(5, 5, 6),
+                ..Default::default()
+            });
+        }
+    }
+    save.repair_brick_count();
+
+    let (below, above) = SaveOctree::split_at(save, Axis::X, 50);
+    assert_eq!(below.bricks.len(), 50);
+    assert_eq!(above.bricks.len(), 50);
+    assert_eq!(below.header1.brick_count, 50);
+    assert_eq!(above.header1.brick_count, 50);
+    assert!(below.bricks.iter().all(|brick| brick.position.0 < 50));
+    assert!(above.bricks.iter().all(|brick| brick.position.0 >= 50));
+}
+
+#[test]
+fn bricks_in_z_range_matches_a_linear_scan() {
+    let mut save = SaveData::default();
+    for i in -30..30 {
+        save.bricks.push(Brick {
+            position: (i * 137, -i * 211, i * 97 + 2),
+            size: Size::Procedural(5, 5, 2),
+            ..Default::default()
+        });
+    }
+    let octree = save.clone().into_octree();
+
+    for (min_z, max_z) in [(-3000, 3000), (-1024, 0), (2, 3), (100, 2000), (5, 5)] {
+        let expected = save
+            .bricks
+            .iter()
+            .filter(|brick| (min_z..max_z).contains(&brick.position.2))
+            .collect::<Vec<_>>();
+        assert_eq!(octree.bricks_in_z_range(min_z, max_z), expected);
+    }
+    assert_eq!(octree.bricks_at_z(2), vec![&save.bricks[30]]);
+}
+
+#[test]
+fn adjacency_graph_of_a_grid() {
+    let mut save = SaveData::default();
+    for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        save.bricks.push(Brick {
+            position: (x * 10 + 5, y * 10 + 5, 6),
+            size: Size::Procedural(5, 5, 6),
+            ..Default::default()
+        });
+    }
+    let octree = save.into_octree();
+
+    assert_eq!(
+        octree.adjacency_graph(),
+        vec![vec![1, 2], vec![0, 3], vec![0, 3], vec![1, 2]]
+    );
+    assert_eq!(
+        octree.adjacency_graph_directed(Direction::XPositive),
+        vec![vec![1], vec![], vec![3], vec![]]
+    );
+    assert_eq!(
+        octree.adjacency_graph_directed(Direction::YNegative),
+        vec![vec![], vec![], vec![0], vec![1]]
+    );
+    assert_eq!(
+        octree.adjacency_graph_directed(Direction::ZPositive),
+        vec![Vec::<usize>::new(); 4]
+    );
+}
+
+#[test]
+fn chunk_tree_iterates_every_value_once() {
+    let mut tree = ChunkTree::new();
+    for i in 0..5 {
+        let min = Point::new(i * 4, 2, 2);
+        tree.insert(i, min, min.shifted(2, 2, 2));
+    }
+
+    let mut values = tree.values().copied().collect::<Vec<_>>();
+    values.sort_unstable();
+    assert_eq!(values, vec![0, 1, 2, 3, 4]);
+    assert_eq!(tree.iter().count(), 5);
+
+    let mut values = tree.into_values().collect::<Vec<_>>();
+    values.sort_unstable();
+    assert_eq!(values, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn node_count_counts_occupied_leaves() {
+    // the smallest leaves are 2 units wide, so this is a 4x4x4 grid of leaves
+    let mut full = Node::new(Point::new(4, 4, 4), 3, None);
+    let mut value = 0;
+    for x in 0..4 {
+        for y in 0..4 {
+            for z in 0..4 {
+                let min = Point::new(x * 2, y * 2, z * 2);
+                full.insert(value, min, min.shifted(2, 2, 2));
+                value += 1;
+            }
+        }
+    }
+    assert_eq!(full.count(), 64);
+
+    let mut empty = Node::<usize>::new(Point::new(4, 4, 4), 3, None);
+    empty.value = NodeValue::Children(
+        (0..8)
+            .map(|i| {
+                Node::new(
+                    Point::new(2 + i % 2 * 4, 2 + i / 2 % 2 * 4, 2 + i / 4 * 4),
+                    2,
+                    None,
+                )
+            })
+            .collect(),
+    );
+    assert_eq!(empty.count(), 0);
+}
+
+#[test]
+fn fill_below_stacks_plates_down_to_the_floor() {
+    let mut save = SaveData::default();
+    save.header2.brick_owners = vec![User::default().into()];
+    // the bottom of this brick is at Z = 100
+    save.bricks.push(Brick {
+        size: Size::Procedural(5, 5, 6),
+        position: (0, 0, 106),
+        owner_index: 1,
+        ..Default::default()
+    });
+    save.fill_below(0, 0);
+
+    let fill = &save.bricks[1..];
+    assert_eq!(fill.len(), 25);
+    assert!(fill
+        .iter()
+        .all(|brick| brick.size == Size::Procedural(5, 5, 2) && brick.owner_index == 1));
+    assert_eq!(fill[0].position, (0, 0, 98));
+    assert_eq!(fill[24].position, (0, 0, 2));
+    assert_eq!(save.header2.brick_owners[0].bricks, 26);
+    assert_eq!(save.header1.brick_count, 26);
+}
+
+#[test]
+fn fill_below_columns_do_not_overlap() {
+    let mut save = SaveData::default();
+    // two floating bricks at different heights whose footprints overlap on X
+    for (x, z) in [(0, 40), (5, 20)] {
+        save.bricks.push(Brick {
+            size: Size::Procedural(10, 10, 2),
+            position: (x, 0, z),
+            ..Default::default()
+        });
+    }
+    save.fill_below(0, 0);
+    assert_eq!(save.bricks.len(), 2 + 4 + 5);
+
+    let bounds = |brick: &Brick| {
+        let Size::Procedural(x, y, z) = brick.size else {
+            unreachable!()
+        };
+        let (x, y, z) = (x as i32, y as i32, z as i32);
+        let (px, py, pz) = brick.position;
+        ((px - x, py - y, pz - z), (px + x, py + y, pz + z))
+    };
+    for (i, a) in save.bricks.iter().enumerate() {
+        for b in &save.bricks[i + 1..] {
+            let ((a_min, a_max), (b_min, b_max)) = (bounds(a), bounds(b));
+            let overlaps = a_min.0 < b_max.0
+                && b_min.0 < a_max.0
+                && a_min.1 < b_max.1
+                && b_min.1 < a_max.1
+                && a_min.2 < b_max.2
+                && b_min.2 < a_max.2;
+            assert!(!overlaps, "{:?} overlaps {:?}", a, b);
+        }
+    }
+}
+
+#[test]
+fn bricks_in_cylinder_includes_the_radius() {
+    let mut save = SaveData::default();
+    for position in [
+        (0, 0, 6),
+        (30, 40, 6),
+        (31, 40, 6),
+        (-50, 0, 6),
+        (0, 0, 100),
+        (0, -49, 500),
+    ] {
+        save.bricks.push(Brick {
+            size: Size::Procedural(4, 4, 4),
+            position,
+            ..Default::default()
+        });
+    }
+    let octree = save.into_octree();
+    let positions = |bricks: Vec<&Brick>| bricks.iter().map(|b| b.position).collect::<Vec<_>>();
+
+    // (30, 40) is exactly 50 away, (31, 40) is just outside
+    assert_eq!(
+        positions(octree.bricks_in_cylinder(0, 0, 0, 10, 50)),
+        vec![(0, 0, 6), (30, 40, 6), (-50, 0, 6)]
+    );
+    assert_eq!(
+        positions(octree.bricks_in_cylinder(0, 0, 6, 100, 49)),
+        vec![(0, 0, 6), (0, 0, 100)]
+    );
+    assert_eq!(
+        positions(octree.bricks_in_circle(0, 0, 50)),
+        vec![
+            (0, 0, 6),
+            (30, 40, 6),
+            (-50, 0, 6),
+            (0, 0, 100),
+            (0, -49, 500)
+        ]
+    );
+    assert_eq!(
+        positions(octree.bricks_in_circle(31, 40, 0)),
+        vec![(31, 40, 6)]
+    );
+    assert!(octree.bricks_in_circle(0, 0, -1).is_empty());
+}
+
+#[test]
+fn data_mut_requires_a_rebuild_before_querying() {
+    let brick = |position| Brick {
+        position,
+        size: Size::Procedural(4, 4, 4),
+        ..Default::default()
+    };
+    let save = SaveData {
+        bricks: vec![brick((100, 100, 100)), brick((120, 100, 100))],
+        ..Default::default()
+    };
+    // moving a brick without removing any still makes the tree stale
+    let mut moved = save.clone().into_octree();
+    moved.data_mut().bricks[0].position = (5000, 5000, 5000);
+    let stale = std::panic::catch_unwind(|| moved.bricks_in((0, 0, 0), (200, 200, 200)).len());
+    assert!(stale.is_err());
+    moved.ensure_built();
+    assert_eq!(moved.bricks_in((0, 0, 0), (200, 200, 200)).len(), 1);
+
+    let mut octree = save.into_octree();
+    assert_eq!(octree.bricks_in((0, 0, 0), (200, 200, 200)).len(), 2);
+
+    octree.data_mut().bricks.remove(0);
+    octree.data_mut().bricks[0].position = (500, 500, 500);
+    let stale = std::panic::catch_unwind(|| octree.bricks_in((0, 0, 0), (200, 200, 200)).len());
+    assert!(stale.is_err());
+
+    octree.ensure_built();
+    assert!(octree.bricks_in((0, 0, 0), (200, 200, 200)).is_empty());
+    assert_eq!(octree.brick_at_exact((500, 500, 500)).len(), 1);
+    // an up to date octree isn't rebuilt again
+    octree.ensure_built();
+    assert_eq!(octree.iter_chunks().count(), 1);
+}
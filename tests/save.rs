@@ -0,0 +1,829 @@
+use std::collections::HashMap;
+
+use brickadia::{save::*, write::SaveWriter};
+
+#[test]
+fn repair_indices_drops_unlisted_components() {
+    let mut save = SaveData::default();
+    for i in 0..4 {
+        let brick = Brick {
+            position: (i * 10, 0, 6),
+            ..Default::default()
+        };
+        save.bricks
+            .push(brick.with_component("BCD_PointLight", HashMap::new()));
+    }
+    save.components.insert(
+        "BCD_PointLight".into(),
+        Component {
+            brick_indices: vec![1, 3, 7],
+            ..Default::default()
+        },
+    );
+
+    let report = save.repair_indices();
+    assert_eq!(save.components["BCD_PointLight"].brick_indices, vec![1, 3]);
+    let with_light = save
+        .bricks
+        .iter()
+        .map(|brick| brick.components.contains_key("BCD_PointLight"))
+        .collect::<Vec<_>>();
+    assert_eq!(with_light, vec![false, true, false, true]);
+    assert_eq!(report.changes.len(), 3);
+}
+
+/// Write `save` and return the size of the written bytes.
+fn written_size(save: &SaveData) -> usize {
+    let mut bytes = vec![];
+    SaveWriter::new(&mut bytes, save.clone()).write().unwrap();
+    bytes.len()
+}
+
+#[test]
+fn sort_bricks_for_compression_shrinks_and_keeps_components() {
+    let mut save = SaveData::default();
+    save.header2.brick_assets = (0..8).map(|i| format!("PB_Asset{}", i)).collect();
+    save.header2.materials = (0..4).map(|i| format!("BMC_Material{}", i)).collect();
+    let mut light = Component {
+        properties: HashMap::from([("Brightness".to_string(), "Float".to_string())]),
+        ..Default::default()
+    };
+
+    let mut seed: u64 = 12345;
+    for i in 0..20000 {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        let mut brick = Brick {
+            asset_name_index: (seed >> 33) as u32 % 8,
+            material_index: (seed >> 40) as u32 % 4,
+            position: ((i % 100) * 10, (i / 100) * 10, (seed >> 50) as i32 % 5 * 4),
+            size: Size::Procedural(5, 5, 2),
+            ..Default::default()
+        };
+        if i % 7 == 0 {
+            let brightness = UnrealType::Float(i as f32);
+            brick.add_component_property("BCD_PointLight", "Brightness", brightness);
+            light.brick_indices.push(i as u32);
+        }
+        save.bricks.push(brick);
+    }
+    save.components.insert("BCD_PointLight".into(), light);
+
+    let before = written_size(&save);
+    let lights = save.count_bricks_with_component("BCD_PointLight");
+    save.sort_bricks_for_compression();
+    assert!(written_size(&save) < before);
+
+    let indices = &save.components["BCD_PointLight"].brick_indices;
+    assert_eq!(indices.len(), lights);
+    for &i in indices {
+        assert!(save.bricks[i as usize].has_component("BCD_PointLight"));
+    }
+}
+
+#[test]
+fn renaming_names_keeps_brick_indices_valid() {
+    let mut save = SaveData::default();
+    save.header2.materials = vec!["BMC_Plastic".into(), "BMC_Glow".into()];
+    save.header2.mods = vec!["Foo".into()];
+    for i in 0..4 {
+        save.bricks.push(Brick {
+            material_index: i % 2,
+            ..Default::default()
+        });
+    }
+
+    assert!(save.rename_material("BMC_Glow", "BMC_Metallic"));
+    assert!(!save.rename_material("BMC_Glow", "BMC_Glass"));
+    assert!(save.replace_asset("PB_DefaultBrick", "PB_DefaultTile"));
+    assert!(save.replace_mod("Foo", "Bar"));
+    assert_eq!(save.header2.materials, vec!["BMC_Plastic", "BMC_Metallic"]);
+    assert_eq!(save.header2.brick_assets, vec!["PB_DefaultTile"]);
+    assert_eq!(save.header2.mods, vec!["Bar"]);
+    let materials = &save.header2.materials;
+    assert!(save
+        .bricks
+        .iter()
+        .all(|brick| (brick.material_index as usize) < materials.len()));
+}
+
+#[test]
+fn strip_unused_mods_removes_unreferenced_mods() {
+    let mut save = SaveData::default();
+    save.header2.mods = vec!["Foo".into(), "Bar".into()];
+    save.header2.brick_assets.push("Mod_Foo_PB_Wedge".into());
+    save.header2.brick_assets.push("Mod_Bar_PB_Wedge".into());
+    save.bricks.push(Brick {
+        asset_name_index: 1,
+        ..Default::default()
+    });
+
+    assert_eq!(
+        save.required_mods(),
+        ["Foo".to_string()].into_iter().collect()
+    );
+    assert_eq!(save.strip_unused_mods(), 1);
+    assert_eq!(save.header2.mods, vec!["Foo"]);
+}
+
+#[test]
+fn required_mods_match_whole_mod_name_prefixes() {
+    let mut save = SaveData::default();
+    save.header2.mods = vec!["Foo".into(), "Foobar".into(), "PB".into(), "Mod_Baz".into()];
+    save.header2.brick_assets = vec!["PB_DefaultBrick".into(), "Mod_Foobar_PB_X".into()];
+    save.header2.materials = vec!["Mod_Baz_BMC_Glow".into()];
+    for asset_name_index in 0..2 {
+        save.bricks.push(Brick {
+            asset_name_index,
+            ..Default::default()
+        });
+    }
+
+    assert_eq!(
+        save.required_mods(),
+        ["Foobar".to_string(), "Mod_Baz".to_string()]
+            .into_iter()
+            .collect()
+    );
+    assert_eq!(save.strip_unused_mods(), 2);
+    assert_eq!(save.header2.mods, vec!["Foobar", "Mod_Baz"]);
+}
+
+#[test]
+fn map_bricks_offsets_every_position() {
+    let mut save = SaveData::default();
+    for i in 0..5 {
+        save.bricks.push(Brick {
+            position: (i * 10, 0, 6),
+            ..Default::default()
+        });
+    }
+
+    save.map_bricks(|mut brick| {
+        brick.position.2 += 100;
+        brick
+    });
+    save.map_bricks_indexed(|i, mut brick| {
+        brick.position.1 = i as i32;
+        brick
+    });
+    for (i, brick) in save.bricks.iter().enumerate() {
+        assert_eq!(brick.position, (i as i32 * 10, i as i32, 106));
+    }
+    assert_eq!(save.header1.brick_count, 5);
+}
+
+#[test]
+fn brick_volume_does_not_overflow() {
+    let brick = Brick {
+        size: Size::Procedural(100_000, 100_000, 100_000),
+        ..Default::default()
+    };
+    assert_eq!(brick.volume(), 1_000_000_000_000_000);
+    assert_eq!(Brick::default().volume(), 1);
+    assert!(Brick::default().is_visible());
+    assert!(!Brick {
+        visibility: false,
+        ..Default::default()
+    }
+    .is_visible());
+}
+
+#[test]
+fn orientation_matrices_are_rotations() {
+    for direction in 0..6 {
+        for rotation in 0..4 {
+            let brick = Brick {
+                direction: Direction::try_from(direction).unwrap(),
+                rotation: Rotation::try_from(rotation).unwrap(),
+                ..Default::default()
+            };
+            let m = brick.orientation_matrix();
+
+            // M × Mᵀ = I
+            for i in 0..3 {
+                for j in 0..3 {
+                    let dot = (0..3).map(|k| m[i][k] * m[j][k]).sum::<f32>();
+                    assert_eq!(dot, if i == j { 1.0 } else { 0.0 });
+                }
+            }
+
+            // the quaternion describes the same rotation
+            let [x, y, z, w] = brick.orientation_quaternion();
+            let q = [
+                [
+                    1.0 - 2.0 * (y * y + z * z),
+                    2.0 * (x * y - z * w),
+                    2.0 * (x * z + y * w),
+                ],
+                [
+                    2.0 * (x * y + z * w),
+                    1.0 - 2.0 * (x * x + z * z),
+                    2.0 * (y * z - x * w),
+                ],
+                [
+                    2.0 * (x * z - y * w),
+                    2.0 * (y * z + x * w),
+                    1.0 - 2.0 * (x * x + y * y),
+                ],
+            ];
+            for i in 0..3 {
+                for j in 0..3 {
+                    assert!((q[i][j] - m[i][j]).abs() < 1e-5);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn compact_removes_unused_entries() {
+    let red = |r| Color {
+        r,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+    let mut save = SaveData::default();
+    save.header2.colors = vec![red(0), red(1), red(2), red(3)];
+    save.header2.materials = vec!["A".into(), "B".into(), "C".into()];
+    save.header2.physical_materials = vec!["P".into(), "Q".into()];
+    save.bricks = [3, 1]
+        .into_iter()
+        .map(|color| Brick {
+            color: BrickColor::Index(color),
+            material_index: 2,
+            physical_index: 1,
+            ..Default::default()
+        })
+        .collect();
+    save.components.insert("Empty".into(), Component::default());
+
+    let report = save.compact();
+    assert_eq!(
+        report,
+        CompactReport {
+            components: 1,
+            colors: 2,
+            materials: 2,
+            physical_materials: 1,
+        }
+    );
+    assert!(save.components.is_empty());
+    assert_eq!(save.header2.colors, vec![red(1), red(3)]);
+    assert_eq!(save.header2.materials, vec!["C".to_string()]);
+    assert_eq!(save.header2.physical_materials, vec!["Q".to_string()]);
+    assert_eq!(save.bricks[0].color, BrickColor::Index(1));
+    assert_eq!(save.bricks[1].color, BrickColor::Index(0));
+    assert!(save
+        .bricks
+        .iter()
+        .all(|brick| brick.material_index == 0 && brick.physical_index == 0));
+    assert_eq!(save.compact(), CompactReport::default());
+}
+
+#[test]
+fn sort_colors_by_frequency_keeps_resolved_colors() {
+    let red = |r| Color {
+        r,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+    let mut save = SaveData::default();
+    save.header2.colors = vec![red(0), red(1), red(2)];
+    save.bricks = [2, 2, 1, 2, 0, 1]
+        .into_iter()
+        .map(|color| Brick {
+            color: BrickColor::Index(color),
+            ..Default::default()
+        })
+        .collect();
+    let resolved = |save: &SaveData| {
+        save.bricks
+            .iter()
+            .map(|brick| brick.color.clone().into_resolved(&save.header2))
+            .collect::<Vec<_>>()
+    };
+    let before = resolved(&save);
+
+    save.header2.sort_colors_by_frequency(&mut save.bricks);
+    assert_eq!(save.header2.colors, vec![red(2), red(1), red(0)]);
+    assert_eq!(save.bricks[0].color, BrickColor::Index(0));
+    assert_eq!(resolved(&save), before);
+}
+
+#[test]
+fn sort_bricks_by_position_orders_a_shuffled_grid() {
+    let mut positions = vec![];
+    for z in 0..3 {
+        for y in 0..3 {
+            for x in 0..3 {
+                positions.push((x * 10, y * 10, z * 12));
+            }
+        }
+    }
+
+    let mut save = SaveData::default();
+    let mut light = Component::default();
+    // 7 is coprime with 27, so this visits every position once, out of order
+    for i in 0..positions.len() {
+        let position = positions[i * 7 % positions.len()];
+        let mut brick = Brick {
+            position,
+            ..Default::default()
+        };
+        if position == (20, 10, 12) {
+            brick.add_component_property("BCD_PointLight", "Brightness", UnrealType::Float(1.0));
+            light.brick_indices.push(i as u32);
+        }
+        save.bricks.push(brick);
+    }
+    save.components.insert("BCD_PointLight".into(), light);
+
+    save.sort_bricks_by_position();
+    let sorted = save.bricks.iter().map(|b| b.position).collect::<Vec<_>>();
+    assert_eq!(sorted, positions);
+
+    let indices = &save.components["BCD_PointLight"].brick_indices;
+    assert_eq!(indices.len(), 1);
+    assert_eq!(save.bricks[indices[0] as usize].position, (20, 10, 12));
+    assert!(save.bricks[indices[0] as usize].has_component("BCD_PointLight"));
+}
+
+#[test]
+fn orientation_arithmetic() {
+    assert_eq!(Rotation::Deg270.combine(Rotation::Deg180), Rotation::Deg90);
+    assert_eq!(
+        Direction::XPositive.rotate_by(Rotation::Deg90),
+        Direction::YPositive
+    );
+    assert_eq!(
+        Direction::YPositive.rotate_by(Rotation::Deg90),
+        Direction::XNegative
+    );
+    assert_eq!(
+        Direction::ZNegative.rotate_by(Rotation::Deg90),
+        Direction::ZNegative
+    );
+    assert_eq!(
+        Direction::ZPositive.rotate_around(Direction::XPositive, Rotation::Deg90),
+        Direction::YNegative
+    );
+    assert_eq!(
+        Direction::YPositive.rotate_around(Direction::XPositive, Rotation::Deg90),
+        Direction::ZPositive
+    );
+
+    for direction in 0..6 {
+        let direction = Direction::try_from(direction).unwrap();
+        for rotation in 0..4 {
+            let inverse = Rotation::try_from((4 - rotation) % 4).unwrap();
+            let rotation = Rotation::try_from(rotation).unwrap();
+            assert_eq!(direction.rotate_around(direction, rotation), direction);
+            assert_eq!(direction.rotate_by(rotation).rotate_by(inverse), direction);
+            assert_eq!(rotation.combine(inverse), Rotation::Deg0);
+        }
+    }
+}
+
+#[cfg(feature = "util")]
+#[test]
+fn orientation_compose_matches_combine_around_z() {
+    use brickadia::util::rotation::orientation_compose;
+
+    for rotation in 0..4 {
+        let rotation = Rotation::try_from(rotation).unwrap();
+        for other in 0..4 {
+            let other = Rotation::try_from(other).unwrap();
+            assert_eq!(
+                orientation_compose(Direction::ZPositive, rotation, Direction::ZPositive, other),
+                (Direction::ZPositive, rotation.combine(other))
+            );
+        }
+    }
+}
+
+/// A save with a visible, an invisible, a zero size, and another visible brick, each with
+/// a light, and two colors used only by the invisible and zero size bricks.
+fn cluttered_save() -> SaveData {
+    let mut save = SaveData::default();
+    save.header2.colors = (0..3).map(Color::from_u32_rgba).collect();
+    save.bricks = vec![
+        Brick {
+            position: (1, 0, 0),
+            ..Default::default()
+        },
+        Brick {
+            visibility: false,
+            color: BrickColor::Index(1),
+            ..Default::default()
+        },
+        Brick {
+            size: Size::Procedural(0, 0, 0),
+            color: BrickColor::Index(2),
+            ..Default::default()
+        },
+        Brick {
+            position: (2, 0, 0),
+            ..Default::default()
+        },
+    ];
+    let mut light = Component::default();
+    for (i, brick) in save.bricks.iter_mut().enumerate() {
+        let brightness = UnrealType::Float(i as f32);
+        brick.add_component_property("BCD_PointLight", "Brightness", brightness);
+        light.brick_indices.push(i as u32);
+    }
+    save.components.insert("BCD_PointLight".into(), light);
+    save.repair_brick_count();
+    save
+}
+
+/// The brightness of each brick's light, which identifies the brick.
+fn brightnesses(save: &SaveData) -> Vec<UnrealType> {
+    save.bricks
+        .iter()
+        .map(|brick| brick.components["BCD_PointLight"]["Brightness"].clone())
+        .collect()
+}
+
+#[test]
+fn remove_invisible_and_zero_size_bricks() {
+    let mut save = cluttered_save();
+    assert_eq!(save.remove_invisible_bricks(), 1);
+    assert_eq!(save.remove_invisible_bricks(), 0);
+    assert_eq!(
+        brightnesses(&save),
+        vec![
+            UnrealType::Float(0.0),
+            UnrealType::Float(2.0),
+            UnrealType::Float(3.0)
+        ]
+    );
+    assert_eq!(
+        save.components["BCD_PointLight"].brick_indices,
+        vec![0, 1, 2]
+    );
+
+    let mut save = cluttered_save();
+    assert_eq!(save.remove_zero_size_bricks(), 1);
+    assert_eq!(
+        brightnesses(&save),
+        vec![
+            UnrealType::Float(0.0),
+            UnrealType::Float(1.0),
+            UnrealType::Float(3.0)
+        ]
+    );
+    assert_eq!(
+        save.components["BCD_PointLight"].brick_indices,
+        vec![0, 1, 2]
+    );
+}
+
+#[test]
+fn cleanup_removes_bricks_and_compacts() {
+    let mut save = cluttered_save();
+    let report = save.cleanup();
+    assert_eq!(report.invisible_bricks, 1);
+    assert_eq!(report.zero_size_bricks, 1);
+    assert_eq!(report.compact.colors, 2);
+    assert_eq!(save.header2.colors, vec![Color::from_u32_rgba(0)]);
+    assert_eq!(save.header1.brick_count, 2);
+
+    assert_eq!(
+        brightnesses(&save),
+        vec![UnrealType::Float(0.0), UnrealType::Float(3.0)]
+    );
+    assert_eq!(save.components["BCD_PointLight"].brick_indices, vec![0, 1]);
+}
+
+#[test]
+fn center_of_mass_weights_by_volume() {
+    let mut save = SaveData::default();
+    assert_eq!(save.center_of_mass(), None);
+
+    // bricks this large have a volume far past u32::MAX
+    let id = Uuid::from_u128(5);
+    save.header2.brick_owners = vec![User {
+        name: "a".into(),
+        id,
+    }
+    .into()];
+    for (x, y) in [(-10, -10), (10, -10), (-10, 10), (10, 10)] {
+        save.bricks.push(Brick {
+            size: Size::Procedural(50_000, 50_000, 60_000),
+            position: (x + 100, y, 6),
+            owner_index: 1,
+            ..Default::default()
+        });
+    }
+    assert_eq!(save.center_of_mass(), Some((100.0, 0.0, 6.0)));
+
+    save.bricks.push(Brick {
+        size: Size::Procedural(1, 1, 1),
+        position: (1_000_000, 0, 0),
+        ..Default::default()
+    });
+    assert_eq!(save.center_of_mass_by_owner(id), Some((100.0, 0.0, 6.0)));
+    let (x, _, _) = save.center_of_mass().unwrap();
+    let big = 4.0 * 100_000.0 * 100_000.0 * 120_000.0;
+    assert!((x - (100.0 * big + 1_000_000.0 * 8.0) / (big + 8.0)).abs() < 1e-9);
+    assert_eq!(save.center_of_mass_by_owner(Uuid::from_u128(9)), None);
+}
+
+#[test]
+fn from_template_keeps_headers_without_bricks() {
+    let mut save = SaveData::default();
+    save.header1.map = "Plate".into();
+    save.header1.description = "A template".into();
+    save.header2.colors = vec![Color::from_u32_rgba(7)];
+    save.header2.materials.push("BMC_Glow".into());
+    save.header2.brick_owners = vec![BrickOwner::from_user_bricks(User::default(), 1)];
+    save.bricks.push(Brick {
+        owner_index: 1,
+        ..Default::default()
+    });
+    save.components
+        .insert("BCD_PointLight".into(), Component::default());
+    save.repair_brick_count();
+
+    let empty = SaveData::from_template(&save);
+    assert!(empty.bricks.is_empty());
+    assert!(empty.components.is_empty());
+    assert_eq!(empty.header1.brick_count, 0);
+    assert_eq!(empty.header1.map, save.header1.map);
+    assert_eq!(empty.header1.description, save.header1.description);
+    assert_eq!(empty.header2.colors, save.header2.colors);
+    assert_eq!(empty.header2.materials, save.header2.materials);
+    assert_eq!(empty.header2.brick_owners.len(), 1);
+    assert_eq!(empty.header2.brick_owners[0].bricks, 0);
+}
+
+#[test]
+fn extremal_bricks_prefer_the_lowest_index() {
+    let mut save = SaveData::default();
+    assert!(save.highest_brick().is_none());
+    assert!(save.bricks_at_extremal_z(Extremal::Max).is_empty());
+
+    save.bricks = [(0, 0, 5), (10, -3, 20), (-7, 8, 20), (2, 2, -4), (1, 1, -4)]
+        .into_iter()
+        .map(|position| Brick {
+            position,
+            ..Default::default()
+        })
+        .collect();
+    let index = |brick: Option<(usize, &Brick)>| brick.unwrap().0;
+    assert_eq!(index(save.highest_brick()), 1);
+    assert_eq!(index(save.lowest_brick()), 3);
+    assert_eq!(index(save.extremal_brick(0, 1)), 1);
+    assert_eq!(index(save.extremal_brick(0, -1)), 2);
+    assert_eq!(index(save.extremal_brick(1, 1)), 2);
+    assert_eq!(index(save.extremal_brick(1, 0)), 1);
+
+    let indices = |bricks: Vec<(usize, &Brick)>| bricks.iter().map(|b| b.0).collect::<Vec<_>>();
+    assert_eq!(
+        indices(save.bricks_at_extremal_z(Extremal::Max)),
+        vec![1, 2]
+    );
+    assert_eq!(
+        indices(save.bricks_at_extremal_z(Extremal::Min)),
+        vec![3, 4]
+    );
+}
+
+#[test]
+fn merge_owners_combines_duplicate_ids() {
+    let owner = |name: &str, id, bricks| BrickOwner {
+        name: name.into(),
+        id: Uuid::from_u128(id),
+        bricks,
+    };
+    let mut save = SaveData::default();
+    save.header2.brick_owners = vec![
+        owner("a", 1, 2),
+        owner("b", 2, 1),
+        owner("a2", 1, 1),
+        owner("c", 3, 1),
+    ];
+    save.bricks = [1, 2, 3, 4, 1, 0]
+        .into_iter()
+        .map(|owner_index| Brick {
+            owner_index,
+            ..Default::default()
+        })
+        .collect();
+    let mut resolved = save.clone();
+
+    save.merge_owners();
+    let owners = &save.header2.brick_owners;
+    assert_eq!(owners.len(), 3);
+    assert_eq!((owners[0].name.as_str(), owners[0].bricks), ("a", 3));
+    assert_eq!((owners[1].name.as_str(), owners[1].bricks), ("b", 1));
+    assert_eq!((owners[2].name.as_str(), owners[2].bricks), ("c", 1));
+    let indices = save
+        .bricks
+        .iter()
+        .map(|b| b.owner_index)
+        .collect::<Vec<_>>();
+    assert_eq!(indices, vec![1, 2, 1, 3, 1, 0]);
+
+    resolved.merge_owners_with_resolution(|_, duplicate| duplicate.clone());
+    assert_eq!(resolved.header2.brick_owners[0].name, "a2");
+    assert_eq!(resolved.header2.brick_owners[0].bricks, 3);
+}
+
+#[cfg(feature = "util")]
+#[test]
+fn apply_rotation_matches_every_orientation() {
+    use brickadia::util::get_axis_size;
+
+    let size = || Size::Procedural(2, 4, 6);
+    assert_eq!(size().swap_xy(), Size::Procedural(4, 2, 6));
+    assert_eq!(Size::Empty.swap_xy(), Size::Empty);
+    assert_eq!(
+        size().apply_rotation(Direction::ZPositive, Rotation::Deg90),
+        Size::Procedural(4, 2, 6)
+    );
+    assert_eq!(
+        size().apply_rotation(Direction::XPositive, Rotation::Deg0),
+        Size::Procedural(6, 4, 2)
+    );
+
+    for direction in 0..6 {
+        for rotation in 0..4 {
+            let brick = Brick {
+                size: size(),
+                direction: Direction::try_from(direction).unwrap(),
+                rotation: Rotation::try_from(rotation).unwrap(),
+                ..Default::default()
+            };
+            let [x, y, z] = [0, 1, 2].map(|axis| get_axis_size(&brick, &[], axis));
+            let rotated = size().apply_rotation(brick.direction, brick.rotation);
+            assert_eq!(rotated, Size::Procedural(x, y, z));
+
+            let mut sorted = [x, y, z];
+            sorted.sort_unstable();
+            assert_eq!(sorted, [2, 4, 6]);
+        }
+    }
+}
+
+#[test]
+fn headers_compare_users_by_name_and_id() {
+    let mut save = SaveData::default();
+    save.header2.brick_owners = vec![BrickOwner::from_user_bricks(User::default(), 1)];
+
+    let mut renamed = save.clone();
+    renamed.header1.author.name = "Someone else".into();
+    assert_eq!(renamed.header1.author, save.header1.author);
+    assert_ne!(renamed.header1, save.header1);
+    assert_ne!(renamed, save);
+
+    let mut hosted = save.clone();
+    hosted.header1.host = Some(User::default());
+    assert_ne!(hosted.header1, save.header1);
+
+    let mut recounted = save.clone();
+    recounted.header2.brick_owners[0].bricks = 2;
+    assert_ne!(recounted.header2, save.header2);
+    assert_ne!(recounted, save);
+
+    assert_eq!(save.clone(), save);
+}
+
+#[test]
+fn remove_and_rename_component_on_every_brick() {
+    let mut save = SaveData::default();
+    for i in 0..10 {
+        let brightness = HashMap::from([("Brightness".to_string(), UnrealType::Float(i as f32))]);
+        save.bricks
+            .push(Brick::default().with_component("BCD_SpotLight", brightness));
+    }
+    save.components.insert(
+        "BCD_SpotLight".into(),
+        Component::default().with_brick_indices((0..10).collect()),
+    );
+
+    let mut removed = save.clone();
+    assert!(removed.remove_component("BCD_SpotLight"));
+    assert!(!removed.remove_component("BCD_SpotLight"));
+    assert!(removed.components.is_empty());
+    assert!(removed.bricks.iter().all(|b| b.components.is_empty()));
+
+    let mut renamed = save;
+    assert!(renamed.rename_component("BCD_SpotLight", "BCD_PointLight"));
+    assert!(!renamed.rename_component("BCD_SpotLight", "BCD_Other"));
+    assert_eq!(
+        renamed.components["BCD_PointLight"].brick_indices,
+        (0..10).collect::<Vec<_>>()
+    );
+    for (i, brick) in renamed.bricks.iter().enumerate() {
+        assert!(!brick.has_component("BCD_SpotLight"));
+        let light = &brick.components["BCD_PointLight"];
+        assert_eq!(light["Brightness"], UnrealType::Float(i as f32));
+    }
+
+    // renaming onto an existing component is refused
+    renamed
+        .components
+        .insert("Other".into(), Component::default());
+    assert!(!renamed.rename_component("BCD_PointLight", "Other"));
+    assert!(renamed.components.contains_key("BCD_PointLight"));
+    assert!(renamed.rename_component("Other", "Other"));
+}
+
+#[test]
+fn scale_procedural_bricks_doubles_size_and_position() {
+    let mut save = SaveData::default();
+    save.bricks.push(Brick {
+        size: Size::Procedural(5, 5, 6),
+        position: (10, -5, 6),
+        ..Default::default()
+    });
+    save.bricks.push(Brick {
+        size: Size::Empty,
+        position: (1, 2, 3),
+        ..Default::default()
+    });
+    save.scale_procedural_bricks(2.0).unwrap();
+    assert_eq!(save.bricks[0].size, Size::Procedural(10, 10, 12));
+    assert_eq!(save.bricks[0].position, (20, -10, 12));
+    assert_eq!(save.bricks[1].size, Size::Empty);
+    assert_eq!(save.bricks[1].position, (1, 2, 3));
+
+    let before = save.clone();
+    assert_eq!(
+        save.scale_procedural_bricks(0.01),
+        Err(ScaleError::NonPositiveSize(0))
+    );
+    assert_eq!(save, before);
+}
+
+#[test]
+fn snap_bricks_to_plate_grid() {
+    let mut save = SaveData::default();
+    for position in [(13, 17, 7), (10, 20, 8), (-13, -15, -6), (5, 0, 2)] {
+        save.bricks.push(Brick {
+            position,
+            ..Default::default()
+        });
+    }
+    save.snap_bricks_to_grid(10, 4);
+    assert_eq!(save.bricks[0].position, (10, 20, 8));
+    assert_eq!(save.bricks[1].position, (10, 20, 8));
+    assert_eq!(save.bricks[2].position, (-10, -10, -4));
+    assert_eq!(save.bricks[3].position, (10, 0, 4));
+
+    // a zero snap leaves positions alone
+    save.bricks[0].position = (13, 17, 7);
+    save.snap_bricks_to_grid(0, 0);
+    assert_eq!(save.bricks[0].position, (13, 17, 7));
+}
+
+#[test]
+fn clone_every_other_brick() {
+    let mut save = SaveData::default();
+    save.header2.brick_owners = vec![BrickOwner::from(User {
+        name: "a".into(),
+        id: Uuid::from_u128(1),
+    })];
+    for i in 0..10 {
+        let mut brick = Brick {
+            position: (i * 10, 0, 0),
+            owner_index: (i % 2) as u32,
+            ..Default::default()
+        };
+        if i % 3 == 0 {
+            let brightness =
+                HashMap::from([("Brightness".to_string(), UnrealType::Float(i as f32))]);
+            brick = brick.with_component("BCD_PointLight", brightness);
+        }
+        save.bricks.push(brick);
+    }
+    save.components.insert(
+        "BCD_PointLight".into(),
+        Component::default().with_brick_indices(vec![0, 3, 6, 9]),
+    );
+
+    let indices = (0..10).step_by(2).collect::<Vec<_>>();
+    let clone = save.clone_bricks(&indices);
+    assert_eq!(clone.bricks.len(), 5);
+    assert_eq!(clone.header1.brick_count, 5);
+    assert_eq!(clone.bricks[2].position, (40, 0, 0));
+    assert_eq!(
+        clone.bricks[3].components["BCD_PointLight"]["Brightness"],
+        UnrealType::Float(6.0)
+    );
+    assert_eq!(clone.components["BCD_PointLight"].brick_indices, vec![0, 3]);
+    assert_eq!(clone.header2.brick_owners[0].bricks, 0);
+
+    // order is kept and repeated indices are copied twice
+    let clone = save.clone_bricks(&[9, 1, 9]);
+    assert_eq!(clone.bricks[1].position, (10, 0, 0));
+    assert_eq!(clone.components["BCD_PointLight"].brick_indices, vec![0, 2]);
+    assert_eq!(clone.header2.brick_owners[0].bricks, 3);
+    assert_eq!(save.bricks.len(), 10);
+}